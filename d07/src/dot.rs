@@ -0,0 +1,131 @@
+use crate::{BagGraph, BagId};
+use simple_error::SimpleError;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Subgraph<'a> {
+    All,
+    // the color and every bag that can be inside it
+    ReachableFrom(&'a str),
+    // the color and every bag that can contain it
+    LeadingTo(&'a str),
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn to_dot(graph: &BagGraph, subgraph: Subgraph) -> Result<String, SimpleError> {
+    let find = |color: &str| {
+        graph
+            .id(color)
+            .ok_or_else(|| SimpleError::new(format!("unknown color: {}", color)))
+    };
+
+    let included: HashSet<BagId> = match subgraph {
+        Subgraph::All => (0..graph.len()).collect(),
+        Subgraph::ReachableFrom(color) => {
+            let id = find(color)?;
+            let mut result = graph.descendants(id);
+            result.insert(id);
+            result
+        }
+        Subgraph::LeadingTo(color) => {
+            let id = find(color)?;
+            let mut result = graph.ancestors(id);
+            result.insert(id);
+            result
+        }
+    };
+
+    Ok(Dot { graph, included }.to_string())
+}
+
+// the bags in `included` and the edges between them
+struct Dot<'a> {
+    graph: &'a BagGraph,
+    included: HashSet<BagId>,
+}
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let graph = self.graph;
+        let included = || (0..graph.len()).filter(move |id| self.included.contains(id));
+
+        writeln!(f, "digraph bags {{")?;
+        for id in included() {
+            writeln!(f, "    {};", quote(graph.name(id)))?;
+        }
+        for outer in included() {
+            for (inner, count) in graph.contains(outer) {
+                if self.included.contains(inner) {
+                    writeln!(
+                        f,
+                        "    {} -> {} [label=\"{}\"];",
+                        quote(graph.name(outer)),
+                        quote(graph.name(*inner)),
+                        count
+                    )?;
+                }
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_graph() -> BagGraph {
+        let mut graph = BagGraph::new();
+        graph.add_rule("light red", vec![("shiny gold", 2)]);
+        graph.add_rule("shiny gold", vec![("dark olive", 1), ("vibrant plum", 2)]);
+        graph.add_rule("dark olive", vec![]);
+        graph.add_rule("vibrant plum", vec![]);
+        graph
+    }
+
+    #[test]
+    fn dot_all() {
+        assert_eq!(
+            to_dot(&test_graph(), Subgraph::All),
+            Ok([
+                "digraph bags {",
+                "    \"light red\";",
+                "    \"shiny gold\";",
+                "    \"dark olive\";",
+                "    \"vibrant plum\";",
+                "    \"light red\" -> \"shiny gold\" [label=\"2\"];",
+                "    \"shiny gold\" -> \"dark olive\" [label=\"1\"];",
+                "    \"shiny gold\" -> \"vibrant plum\" [label=\"2\"];",
+                "}",
+                "",
+            ]
+            .join("\n"))
+        );
+    }
+
+    #[test]
+    fn dot_reachable_from() {
+        let dot = to_dot(&test_graph(), Subgraph::ReachableFrom("shiny gold")).unwrap();
+        assert!(!dot.contains("light red"));
+        assert!(dot.contains("\"shiny gold\" -> \"vibrant plum\" [label=\"2\"];"));
+    }
+
+    #[test]
+    fn dot_leading_to() {
+        let dot = to_dot(&test_graph(), Subgraph::LeadingTo("shiny gold")).unwrap();
+        assert!(dot.contains("\"light red\" -> \"shiny gold\" [label=\"2\"];"));
+        assert!(!dot.contains("dark olive"));
+    }
+
+    #[test]
+    fn dot_unknown_color() {
+        assert_eq!(
+            to_dot(&test_graph(), Subgraph::LeadingTo("faded blue")),
+            Err(SimpleError::new("unknown color: faded blue"))
+        );
+    }
+}
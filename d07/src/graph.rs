@@ -0,0 +1,462 @@
+use crate::Mapping;
+use simple_error::SimpleError;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+
+pub type BagId = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, BagId>,
+    // colors that have their own rule, not only appear inside another one
+    defined: Vec<bool>,
+    // forward edges: bag -> (inner bag, count)
+    contains: Vec<Vec<(BagId, usize)>>,
+    // reverse edges: bag -> (outer bag, count)
+    contained_in: Vec<Vec<(BagId, usize)>>,
+}
+
+impl BagGraph {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_mapping(mapping: &Mapping) -> Self {
+        let mut graph = BagGraph::new();
+
+        // sort the colors so the IDs don't depend on the hashmap order
+        let mut containers: Vec<&String> = mapping.keys().collect();
+        containers.sort();
+
+        for container in containers {
+            let mut contents: Vec<(&String, &usize)> = mapping[container].iter().collect();
+            contents.sort();
+
+            graph.add_rule(
+                container,
+                contents
+                    .into_iter()
+                    .map(|(color, count)| (&color[..], *count)),
+            );
+        }
+
+        graph
+    }
+
+    pub fn intern(&mut self, color: &str) -> BagId {
+        if let Some(id) = self.ids.get(color) {
+            return *id;
+        }
+
+        let id = self.names.len();
+        self.names.push(color.to_owned());
+        self.ids.insert(color.to_owned(), id);
+        self.defined.push(false);
+        self.contains.push(Vec::new());
+        self.contained_in.push(Vec::new());
+        id
+    }
+
+    // A second rule for the same container replaces the first one.
+    pub fn add_rule<'a>(
+        &mut self,
+        container: &str,
+        contents: impl IntoIterator<Item = (&'a str, usize)>,
+    ) -> BagId {
+        let outer = self.intern(container);
+        self.defined[outer] = true;
+
+        for (inner, _) in mem::take(&mut self.contains[outer]) {
+            self.contained_in[inner].retain(|(id, _)| *id != outer);
+        }
+
+        for (color, count) in contents {
+            let inner = self.intern(color);
+            self.contains[outer].push((inner, count));
+            self.contained_in[inner].push((outer, count));
+        }
+
+        outer
+    }
+
+    pub fn id(&self, color: &str) -> Option<BagId> {
+        self.ids.get(color).copied()
+    }
+
+    pub fn name(&self, id: BagId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn contains(&self, id: BagId) -> &[(BagId, usize)] {
+        &self.contains[id]
+    }
+
+    pub fn contained_in(&self, id: BagId) -> &[(BagId, usize)] {
+        &self.contained_in[id]
+    }
+
+    fn reachable(edges: &[Vec<(BagId, usize)>], start: BagId) -> HashSet<BagId> {
+        let mut result: HashSet<BagId> = HashSet::new();
+        let mut todo: Vec<BagId> = vec![start];
+
+        while let Some(current) = todo.pop() {
+            for (next, _) in edges[current].iter() {
+                if *next != start && result.insert(*next) {
+                    todo.push(*next);
+                }
+            }
+        }
+
+        result
+    }
+
+    // all bags that can (eventually) contain the given bag, without the bag
+    // itself even if the rules contain a cycle
+    pub fn ancestors(&self, id: BagId) -> HashSet<BagId> {
+        BagGraph::reachable(&self.contained_in, id)
+    }
+
+    // all bags that can (eventually) be inside the given bag, without the
+    // bag itself
+    pub fn descendants(&self, id: BagId) -> HashSet<BagId> {
+        BagGraph::reachable(&self.contains, id)
+    }
+
+    // outer bags come before the bags they contain.
+    // Returns `None` when the rules contain a cycle.
+    pub fn topological_order(&self) -> Option<Vec<BagId>> {
+        let mut incoming: Vec<usize> = self.contained_in.iter().map(|e| e.len()).collect();
+        let mut todo: VecDeque<BagId> = (0..self.len()).filter(|&id| incoming[id] == 0).collect();
+        let mut result: Vec<BagId> = Vec::with_capacity(self.len());
+
+        while let Some(current) = todo.pop_front() {
+            result.push(current);
+            for (inner, _) in self.contains[current].iter() {
+                incoming[*inner] -= 1;
+                if incoming[*inner] == 0 {
+                    todo.push_back(*inner);
+                }
+            }
+        }
+
+        if result.len() == self.len() {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    // Walks all bags inside `start` depth first without recursion, calling
+    // `finish` for every bag once all bags inside it are finished.
+    fn depth_first(
+        &self,
+        start: BagId,
+        state: &mut [Visit],
+        finish: &mut dyn FnMut(BagId) -> Result<(), SimpleError>,
+    ) -> Result<(), SimpleError> {
+        if state[start] != Visit::New {
+            return Ok(());
+        }
+
+        // every stack entry is a bag and the index of the next edge to follow
+        let mut stack: Vec<(BagId, usize)> = vec![(start, 0)];
+        state[start] = Visit::InProgress;
+
+        while let Some(&(current, edge)) = stack.last() {
+            if let Some(&(inner, _)) = self.contains[current].get(edge) {
+                stack.last_mut().unwrap().1 += 1;
+
+                match state[inner] {
+                    Visit::New => {
+                        state[inner] = Visit::InProgress;
+                        stack.push((inner, 0));
+                    }
+                    Visit::InProgress => {
+                        // `inner` is somewhere on the stack, everything
+                        // from there to here is the cycle.
+                        let mut path: Vec<&str> = stack
+                            .iter()
+                            .skip_while(|(id, _)| *id != inner)
+                            .map(|(id, _)| self.name(*id))
+                            .collect();
+                        path.push(self.name(inner));
+
+                        return Err(SimpleError::new(format!(
+                            "bag rules contain a cycle: {}",
+                            path.join(" -> ")
+                        )));
+                    }
+                    Visit::Done => {}
+                }
+            } else {
+                stack.pop();
+                state[current] = Visit::Done;
+                finish(current)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // colors which are used inside other bags but have no rule of their own
+    pub fn undefined(&self) -> Vec<BagId> {
+        (0..self.len()).filter(|&id| !self.defined[id]).collect()
+    }
+
+    pub fn validate(&self) -> Result<(), SimpleError> {
+        let mut state = vec![Visit::New; self.len()];
+        for id in 0..self.len() {
+            self.depth_first(id, &mut state, &mut |_| Ok(()))?;
+        }
+
+        let undefined: Vec<&str> = self.undefined().iter().map(|id| self.name(*id)).collect();
+        if !undefined.is_empty() {
+            return Err(SimpleError::new(format!(
+                "bag rules missing for: {}",
+                undefined.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    // number of bags inside the given bag, not counting the bag itself.
+    pub fn total_nested(&self, id: BagId) -> Result<usize, SimpleError> {
+        let mut state = vec![Visit::New; self.len()];
+        let mut totals: Vec<usize> = vec![0; self.len()];
+
+        self.depth_first(id, &mut state, &mut |current| {
+            let mut total: usize = 0;
+            for (inner, count) in self.contains[current].iter() {
+                // every inner bag plus whatever is inside it
+                total = totals[*inner]
+                    .checked_add(1)
+                    .and_then(|v| v.checked_mul(*count))
+                    .and_then(|v| v.checked_add(total))
+                    .ok_or_else(|| {
+                        SimpleError::new(format!("too many bags inside {}", self.name(current)))
+                    })?;
+            }
+            totals[current] = total;
+            Ok(())
+        })?;
+
+        Ok(totals[id])
+    }
+
+    // the shortest chain of bags from `from` down to `to`, both included.
+    pub fn shortest_path(&self, from: BagId, to: BagId) -> Option<Vec<BagId>> {
+        let mut previous: HashMap<BagId, BagId> = HashMap::new();
+        let mut todo: VecDeque<BagId> = VecDeque::new();
+        todo.push_back(from);
+
+        while let Some(current) = todo.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                let mut step = to;
+                while step != from {
+                    step = previous[&step];
+                    path.push(step);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (inner, _) in self.contains[current].iter() {
+                if *inner != from && !previous.contains_key(inner) {
+                    previous.insert(*inner, current);
+                    todo.push_back(*inner);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_mapping;
+
+    fn test_graph() -> BagGraph {
+        let lines: Vec<String> = vec![
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "dark orange bags contain 3 bright white bags, 4 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.",
+            "shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.",
+            "dark olive bags contain 3 faded blue bags, 4 dotted black bags.",
+            "vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.",
+            "faded blue bags contain no other bags.",
+            "dotted black bags contain no other bags.",
+        ]
+        .into_iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        BagGraph::from_mapping(&read_mapping(lines.iter()).unwrap())
+    }
+
+    fn names(graph: &BagGraph, ids: impl IntoIterator<Item = BagId>) -> Vec<&str> {
+        let mut result: Vec<&str> = ids.into_iter().map(|id| graph.name(id)).collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn ancestors_and_descendants() {
+        let graph = test_graph();
+        let gold = graph.id("shiny gold").unwrap();
+
+        assert_eq!(
+            names(&graph, graph.ancestors(gold)),
+            vec!["bright white", "dark orange", "light red", "muted yellow"]
+        );
+        assert_eq!(
+            names(&graph, graph.descendants(gold)),
+            vec!["dark olive", "dotted black", "faded blue", "vibrant plum"]
+        );
+    }
+
+    #[test]
+    fn ancestors_with_cycle() {
+        let mut graph = BagGraph::new();
+        graph.add_rule("dark red", vec![("dark blue", 1)]);
+        graph.add_rule("dark blue", vec![("dark red", 1)]);
+        let red = graph.id("dark red").unwrap();
+
+        assert_eq!(names(&graph, graph.ancestors(red)), vec!["dark blue"]);
+        assert_eq!(names(&graph, graph.descendants(red)), vec!["dark blue"]);
+    }
+
+    #[test]
+    fn add_rule_twice() {
+        let mut graph = BagGraph::new();
+        graph.add_rule("shiny gold", vec![("dark red", 2), ("dark blue", 1)]);
+        let gold = graph.add_rule("shiny gold", vec![("dark red", 3)]);
+        let red = graph.id("dark red").unwrap();
+        let blue = graph.id("dark blue").unwrap();
+
+        assert_eq!(graph.contains(gold), &[(red, 3)]);
+        assert_eq!(graph.contained_in(red), &[(gold, 3)]);
+        assert_eq!(graph.contained_in(blue), &[]);
+    }
+
+    #[test]
+    fn total_nested() {
+        let graph = test_graph();
+        assert_eq!(graph.total_nested(graph.id("shiny gold").unwrap()), Ok(32));
+        assert_eq!(graph.total_nested(graph.id("faded blue").unwrap()), Ok(0));
+    }
+
+    #[test]
+    fn topological_order() {
+        let graph = test_graph();
+        let order = graph.topological_order().unwrap();
+        assert_eq!(order.len(), graph.len());
+
+        let position: HashMap<BagId, usize> =
+            order.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        for outer in 0..graph.len() {
+            for (inner, _) in graph.contains(outer) {
+                assert!(position[&outer] < position[inner]);
+            }
+        }
+    }
+
+    #[test]
+    fn topological_order_with_cycle() {
+        let mut graph = BagGraph::new();
+        graph.add_rule("dark red", vec![("dark blue", 1)]);
+        graph.add_rule("dark blue", vec![("dark red", 1)]);
+
+        assert_eq!(graph.topological_order(), None);
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(test_graph().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_cycle() {
+        let mut graph = BagGraph::new();
+        graph.add_rule("shiny gold", vec![("dark red", 2)]);
+        graph.add_rule("dark red", vec![("dark blue", 1)]);
+        graph.add_rule("dark blue", vec![("dark red", 1)]);
+
+        let expected =
+            SimpleError::new("bag rules contain a cycle: dark red -> dark blue -> dark red");
+        assert_eq!(graph.validate(), Err(expected.clone()));
+        assert_eq!(
+            graph.total_nested(graph.id("shiny gold").unwrap()),
+            Err(expected)
+        );
+    }
+
+    #[test]
+    fn validate_undefined() {
+        let mut graph = BagGraph::new();
+        graph.add_rule("shiny gold", vec![("dark red", 2), ("dark blue", 1)]);
+        graph.add_rule("dark red", vec![]);
+
+        assert_eq!(
+            graph.validate(),
+            Err(SimpleError::new("bag rules missing for: dark blue"))
+        );
+    }
+
+    #[test]
+    fn total_nested_overflow() {
+        let mut graph = BagGraph::new();
+        graph.add_rule("shiny gold", vec![("dark red", usize::MAX / 2)]);
+        graph.add_rule("dark red", vec![("dark blue", 3)]);
+        graph.add_rule("dark blue", vec![]);
+
+        assert_eq!(
+            graph.total_nested(graph.id("shiny gold").unwrap()),
+            Err(SimpleError::new("too many bags inside shiny gold"))
+        );
+    }
+
+    #[test]
+    fn shortest_path() {
+        let graph = test_graph();
+        let path = graph
+            .shortest_path(
+                graph.id("light red").unwrap(),
+                graph.id("faded blue").unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            path.iter().map(|id| graph.name(*id)).collect::<Vec<_>>(),
+            vec!["light red", "muted yellow", "faded blue"]
+        );
+
+        assert_eq!(
+            graph.shortest_path(
+                graph.id("faded blue").unwrap(),
+                graph.id("light red").unwrap()
+            ),
+            None
+        );
+    }
+}
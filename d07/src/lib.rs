@@ -0,0 +1,93 @@
+mod dot;
+mod graph;
+mod rule;
+pub use dot::{to_dot, Subgraph};
+pub use graph::{BagGraph, BagId};
+pub use rule::Rule;
+
+use simple_error::SimpleError;
+use std::collections::HashMap;
+
+pub type Mapping = HashMap<String, HashMap<String, usize>>;
+
+pub fn read_mapping<'a>(lines: impl Iterator<Item = &'a String>) -> Result<Mapping, SimpleError> {
+    let mut mapping: Mapping = HashMap::new();
+
+    for (i, line) in lines.enumerate() {
+        // empty lines don't carry any data, everything else has to be a rule
+        if line.is_empty() {
+            continue;
+        }
+
+        let rule: Rule = line
+            .parse()
+            .map_err(|err| SimpleError::new(format!("line {}: {}", i + 1, err)))?;
+
+        if mapping.contains_key(&rule.color) {
+            return Err(SimpleError::new(format!(
+                "line {}: duplicate rule for \"{}\"",
+                i + 1,
+                rule.color
+            )));
+        }
+
+        mapping.insert(rule.color, rule.contents.into_iter().collect());
+    }
+
+    Ok(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &[&str]) -> Vec<String> {
+        input.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn read_mapping_works() {
+        let mapping = read_mapping(
+            lines(&[
+                "bright white bags contain 1 shiny gold bag.",
+                "",
+                "shiny gold bags contain no other bags.",
+            ])
+            .iter(),
+        )
+        .unwrap();
+
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(mapping["bright white"]["shiny gold"], 1);
+        assert!(mapping["shiny gold"].is_empty());
+    }
+
+    #[test]
+    fn read_mapping_errors() {
+        assert_eq!(
+            read_mapping(
+                lines(&[
+                    "bright white bags contain 1 shiny gold bag.",
+                    "shiny gold bags contain 2 dark olive bag."
+                ])
+                .iter()
+            ),
+            Err(SimpleError::new(
+                "line 2: expected \"bags\" for 2 bags: \"2 dark olive bag\""
+            ))
+        );
+
+        assert_eq!(
+            read_mapping(
+                lines(&[
+                    "shiny gold bags contain no other bags.",
+                    "shiny gold bags contain 1 dark olive bag."
+                ])
+                .iter()
+            ),
+            Err(SimpleError::new(
+                "line 2: duplicate rule for \"shiny gold\""
+            ))
+        );
+    }
+}
@@ -1,8 +1,6 @@
-#[macro_use]
-extern crate lazy_static;
-
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use d07::{read_mapping, to_dot, BagGraph, Subgraph};
+use simple_error::SimpleError;
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
@@ -14,87 +12,59 @@ fn main() {
         .map(|line| line.unwrap())
         .collect();
 
-    println!("part 1: {:?}", part_1(input.iter(), "shiny gold"));
-    println!("part 2: {:?}", part_2(input.iter(), "shiny gold"));
-}
-
-lazy_static! {
-    static ref REGEX_CONTENT: Regex = Regex::new(r"(\d+) (\w+ \w+) (bag|bags)\.?").unwrap();
-}
-
-fn read_mapping<'a>(
-    lines: impl Iterator<Item = &'a String>,
-) -> HashMap<String, HashMap<String, usize>> {
-    let mut mapping: HashMap<String, HashMap<String, usize>> = HashMap::new();
-
-    for line in lines {
-        let content: Vec<&str> = line.split(" bags contain ").collect();
-        if content.len() < 2 {
-            continue;
+    // `d07 dot [from|to <color>]` prints the rules as graphviz graph
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|s| &s[..]) == Some("dot") {
+        let subgraph = match (args.get(1).map(|s| &s[..]), args.get(2)) {
+            (Some("from"), Some(color)) => Subgraph::ReachableFrom(color),
+            (Some("to"), Some(color)) => Subgraph::LeadingTo(color),
+            _ => Subgraph::All,
+        };
+
+        match read_mapping(input.iter())
+            .and_then(|mapping| to_dot(&BagGraph::from_mapping(&mapping), subgraph))
+        {
+            Ok(dot) => print!("{}", dot),
+            Err(err) => eprintln!("{}", err),
         }
-
-        mapping.insert(
-            content[0].to_owned(),
-            content[1]
-                .split(", ")
-                .filter_map(|s| REGEX_CONTENT.captures(s))
-                .map(|cap| (cap[2].parse().unwrap(), cap[1].parse().unwrap()))
-                .collect(),
-        );
+        return;
     }
 
-    mapping
+    println!("part 1: {:?}", part_1(input.iter(), "shiny gold"));
+    println!("part 2: {:?}", part_2(input.iter(), "shiny gold"));
 }
 
-fn search_for_colors(
-    mapping: &HashMap<String, HashMap<String, usize>>,
-    result: &mut HashSet<String>,
-    search_for: &str,
-) {
-    let contains_color: HashSet<String> = mapping
-        .iter()
-        .filter(|(_, v)| v.contains_key(search_for))
-        .map(|(k, _)| k.clone())
-        .collect();
-
-    result.extend(contains_color.clone());
+fn part_1<'a>(
+    lines: impl Iterator<Item = &'a String>,
+    search_for_color: &str,
+) -> Result<usize, SimpleError> {
+    let graph = BagGraph::from_mapping(&read_mapping(lines)?);
 
-    if !(contains_color.is_empty()) {
-        for parent_color in contains_color.iter() {
-            search_for_colors(mapping, result, parent_color);
-        }
+    match graph.id(search_for_color) {
+        Some(id) => Ok(graph.ancestors(id).len()),
+        None => Ok(0),
     }
 }
 
-fn part_1<'a>(lines: impl Iterator<Item = &'a String>, search_for_color: &str) -> usize {
-    let mapping = read_mapping(lines);
-
-    let mut result: HashSet<String> = HashSet::new();
-    search_for_colors(&mapping, &mut result, search_for_color);
-    result.len()
-}
-
-fn count_bags(mapping: &HashMap<String, HashMap<String, usize>>, search_for: &str) -> usize {
-    match mapping.get(search_for) {
-        None => 0,
-        Some(children) => {
-            1 + children
-                .iter()
-                .map(|(color, count)| count * count_bags(mapping, color))
-                .sum::<usize>()
-        }
+fn part_2<'a>(
+    lines: impl Iterator<Item = &'a String>,
+    search_for_color: &str,
+) -> Result<usize, SimpleError> {
+    let graph = BagGraph::from_mapping(&read_mapping(lines)?);
+
+    match graph.id(search_for_color) {
+        Some(id) => graph.total_nested(id),
+        None => Err(SimpleError::new(format!(
+            "unknown color: {}",
+            search_for_color
+        ))),
     }
 }
 
-fn part_2<'a>(lines: impl Iterator<Item = &'a String>, search_for_color: &str) -> usize {
-    let mapping = read_mapping(lines);
-
-    count_bags(&mapping, search_for_color) - 1
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lazy_static::lazy_static;
 
     lazy_static! {
         static ref TEST_DATA: Vec<String> = vec![
@@ -127,16 +97,16 @@ mod tests {
 
     #[test]
     fn part_1_works() {
-        assert_eq!(part_1(TEST_DATA.iter(), "shiny gold"), 4);
+        assert_eq!(part_1(TEST_DATA.iter(), "shiny gold"), Ok(4));
     }
 
     #[test]
     fn part_2_works_1() {
-        assert_eq!(part_2(TEST_DATA.iter(), "shiny gold"), 32);
+        assert_eq!(part_2(TEST_DATA.iter(), "shiny gold"), Ok(32));
     }
 
     #[test]
     fn part_2_works_2() {
-        assert_eq!(part_2(TEST_DATA_2.iter(), "shiny gold"), 126);
+        assert_eq!(part_2(TEST_DATA_2.iter(), "shiny gold"), Ok(126));
     }
 }
@@ -0,0 +1,194 @@
+use simple_error::SimpleError;
+use std::str::FromStr;
+
+// One line of the puzzle input:
+//
+//   rule     := color " bags contain " contents "."
+//   contents := "no other bags" | item (", " item)*
+//   item     := count " " color " " ("bag" | "bags")
+//   color    := word (" " word)*
+//
+// `bag` is only allowed for a count of 1, `bags` for everything else.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub color: String,
+    pub contents: Vec<(String, usize)>,
+}
+
+fn parse_color(input: &str) -> Result<String, SimpleError> {
+    if input.is_empty() {
+        return Err(SimpleError::new("missing color"));
+    }
+
+    for word in input.split(' ') {
+        if word.is_empty() {
+            return Err(SimpleError::new(format!("invalid color: \"{}\"", input)));
+        }
+        if !word.chars().all(|c| c.is_alphabetic()) {
+            return Err(SimpleError::new(format!("invalid color: \"{}\"", input)));
+        }
+        if word == "bag" || word == "bags" || word == "contain" {
+            return Err(SimpleError::new(format!(
+                "unexpected \"{}\" in color \"{}\"",
+                word, input
+            )));
+        }
+    }
+
+    Ok(input.to_owned())
+}
+
+fn parse_item(input: &str) -> Result<(String, usize), SimpleError> {
+    let (count_str, rest) = input
+        .split_once(' ')
+        .ok_or_else(|| SimpleError::new(format!("invalid content: \"{}\"", input)))?;
+
+    let count: usize = count_str
+        .parse()
+        .map_err(|_| SimpleError::new(format!("invalid count: \"{}\"", count_str)))?;
+    if count == 0 {
+        return Err(SimpleError::new(format!(
+            "invalid count: \"{}\"",
+            count_str
+        )));
+    }
+
+    let (color, noun) = rest
+        .rsplit_once(' ')
+        .ok_or_else(|| SimpleError::new(format!("invalid content: \"{}\"", input)))?;
+
+    match (count, noun) {
+        (1, "bag") => {}
+        (1, "bags") => {
+            return Err(SimpleError::new(format!(
+                "expected \"bag\" for a single bag: \"{}\"",
+                input
+            )))
+        }
+        (_, "bags") => {}
+        (_, "bag") => {
+            return Err(SimpleError::new(format!(
+                "expected \"bags\" for {} bags: \"{}\"",
+                count, input
+            )))
+        }
+        _ => {
+            return Err(SimpleError::new(format!(
+                "expected \"bag\" or \"bags\": \"{}\"",
+                input
+            )))
+        }
+    }
+
+    Ok((parse_color(color)?, count))
+}
+
+impl FromStr for Rule {
+    type Err = SimpleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (color, contents) = input
+            .split_once(" bags contain ")
+            .ok_or_else(|| SimpleError::new("missing \" bags contain \""))?;
+
+        let contents = contents
+            .strip_suffix('.')
+            .ok_or_else(|| SimpleError::new("missing trailing period"))?;
+
+        let color = parse_color(color)?;
+
+        if contents == "no other bags" {
+            return Ok(Rule {
+                color,
+                contents: Vec::new(),
+            });
+        }
+
+        let mut items: Vec<(String, usize)> = Vec::new();
+        for item in contents.split(", ") {
+            // a comma left in an item means a broken separator
+            if item.contains(',') {
+                return Err(SimpleError::new(format!(
+                    "expected \", \" between contents: \"{}\"",
+                    item
+                )));
+            }
+            let (inner, count) = parse_item(item)?;
+            if items.iter().any(|(c, _)| *c == inner) {
+                return Err(SimpleError::new(format!("duplicate color: \"{}\"", inner)));
+            }
+            items.push((inner, count));
+        }
+
+        Ok(Rule {
+            color,
+            contents: items,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn rule(color: &str, contents: &[(&str, usize)]) -> Rule {
+        Rule {
+            color: color.to_string(),
+            contents: contents.iter().map(|(c, n)| (c.to_string(), *n)).collect(),
+        }
+    }
+
+    #[test_case("faded blue bags contain no other bags.", rule("faded blue", &[]))]
+    #[test_case("bright white bags contain 1 shiny gold bag.", rule("bright white", &[("shiny gold", 1)]))]
+    #[test_case(
+        "muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.",
+        rule("muted yellow", &[("shiny gold", 2), ("faded blue", 9)])
+    )]
+    #[test_case(
+        "very dark red bags contain 3 red bags, 1 very very light blue bag.",
+        rule("very dark red", &[("red", 3), ("very very light blue", 1)])
+    )]
+    fn test_parse_rule(input: &str, expected: Rule) {
+        assert_eq!(input.parse(), Ok(expected));
+    }
+
+    #[test_case("faded blue bags contain no other bags", "missing trailing period")]
+    #[test_case("faded blue bag contains no other bags.", "missing \" bags contain \"")]
+    #[test_case(" bags contain no other bags.", "missing color")]
+    #[test_case("faded blue bags contain no bags.", "invalid count: \"no\"")]
+    #[test_case("faded blue bags contain 0 red bags.", "invalid count: \"0\"")]
+    #[test_case(
+        "faded blue bags contain 1 red bags.",
+        "expected \"bag\" for a single bag: \"1 red bags\""
+    )]
+    #[test_case(
+        "faded blue bags contain 2 red bag.",
+        "expected \"bags\" for 2 bags: \"2 red bag\""
+    )]
+    #[test_case("faded blue bags contain 2 red.", "invalid content: \"2 red\"")]
+    #[test_case(
+        "faded blue bags contain 2 red boxes.",
+        "expected \"bag\" or \"bags\": \"2 red boxes\""
+    )]
+    #[test_case(
+        "faded blue bags contain 2 red bags,3 blue bags.",
+        "expected \", \" between contents: \"2 red bags,3 blue bags\""
+    )]
+    #[test_case(
+        "faded blue bags contain 2 red bags ,3 blue bags.",
+        "expected \", \" between contents: \"2 red bags ,3 blue bags\"";
+        "space before comma"
+    )]
+    #[test_case(
+        "faded blue bags contain 2 red bags,, 3 blue bags.",
+        "expected \", \" between contents: \"2 red bags,\""
+    )]
+    #[test_case(
+        "faded blue bags contain 2 red bags, 2 red bags.",
+        "duplicate color: \"red\""
+    )]
+    fn test_parse_rule_error(input: &str, expected: &str) {
+        assert_eq!(input.parse::<Rule>(), Err(SimpleError::new(expected)));
+    }
+}
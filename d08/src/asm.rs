@@ -0,0 +1,187 @@
+use crate::Command;
+use simple_error::SimpleError;
+use std::collections::{BTreeSet, HashMap};
+
+// Assembly source is the normal boot code with a few additions:
+//
+//   # comments start with `#` or `;` and run to the end of the line
+//   start:            labels end with `:`, either alone on a line
+//   loop: acc +1      or in front of a command
+//       jnz loop      jumps (and `nop`) can use a label instead of an offset
+//
+// Labels are resolved into the relative offsets the console understands.
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_value(value: &str) -> Option<i64> {
+    // `+1` is what the puzzle input uses, `parse` only knows `1`
+    value.strip_prefix('+').unwrap_or(value).parse().ok()
+}
+
+pub fn assemble(source: &str) -> Result<Vec<Command>, SimpleError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    // (source line, op, operand) for every command
+    let mut commands: Vec<(usize, &str, &str)> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let error = |msg: String| SimpleError::new(format!("line {}: {}", i + 1, msg));
+
+        let mut rest = line.split(['#', ';']).next().unwrap().trim();
+
+        while let Some((label, after)) = rest.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(error(format!("invalid label: {}", label)));
+            }
+            if labels.insert(label, commands.len()).is_some() {
+                return Err(error(format!("duplicate label: {}", label)));
+            }
+            rest = after.trim();
+        }
+
+        if rest.is_empty() {
+            continue;
+        }
+
+        match rest.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            [op, operand] => commands.push((i + 1, op, operand)),
+            _ => return Err(error(format!("expected \"<op> <value>\": {}", rest))),
+        }
+    }
+
+    commands
+        .iter()
+        .enumerate()
+        .map(|(index, (line, op, operand))| {
+            let error = |msg: String| SimpleError::new(format!("line {}: {}", line, msg));
+
+            let value = match (parse_value(operand), labels.get(operand)) {
+                (Some(value), _) => value,
+                (None, Some(target)) => *target as i64 - index as i64,
+                (None, None) if is_label(operand) => {
+                    return Err(error(format!("unknown label: {}", operand)))
+                }
+                (None, None) => return Err(error(format!("invalid value: {}", operand))),
+            };
+
+            let command =
+                Command::new(op, value).ok_or_else(|| error(format!("unknown command: {}", op)))?;
+
+            if !(command.is_jump() || matches!(command, Command::Nop(_)))
+                && parse_value(operand).is_none()
+            {
+                return Err(error(format!("{} does not take a label", op)));
+            }
+
+            Ok(command)
+        })
+        .collect()
+}
+
+// Prints the program as assembly, with labels for every jump target inside
+// the program (or right after it). `assemble` turns it back into the same
+// commands.
+pub fn disassemble(program: &[Command]) -> String {
+    let target = |index: usize, command: &Command| {
+        (index as i64)
+            .checked_add(command.value())
+            .filter(|t| *t >= 0 && *t <= program.len() as i64)
+            .map(|t| t as usize)
+    };
+
+    let targets: BTreeSet<usize> = program
+        .iter()
+        .enumerate()
+        .filter(|(_, command)| command.is_jump())
+        .filter_map(|(index, command)| target(index, command))
+        .collect();
+
+    let mut output = String::new();
+    for index in 0..=program.len() {
+        if targets.contains(&index) {
+            output.push_str(&format!("l{}:\n", index));
+        }
+
+        if let Some(command) = program.get(index) {
+            match target(index, command) {
+                Some(t) if command.is_jump() => {
+                    output.push_str(&format!("    {} l{}\n", command.op(), t))
+                }
+                _ => output.push_str(&format!("    {}\n", command)),
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+            # count down from 3
+                acc +3
+            loop: jz done   ; leave when done
+                acc -1
+                jmp loop
+            done:
+        ";
+
+        assert_eq!(
+            assemble(source),
+            Ok(vec![
+                Command::Acc(3),
+                Command::Jz(3),
+                Command::Acc(-1),
+                Command::Jmp(-2),
+            ])
+        );
+    }
+
+    #[test_case("acc", "line 1: expected \"<op> <value>\": acc")]
+    #[test_case("foo +1", "line 1: unknown command: foo")]
+    #[test_case("jmp nowhere", "line 1: unknown label: nowhere")]
+    #[test_case("jmp 1x", "line 1: invalid value: 1x")]
+    #[test_case("a:\nacc a", "line 2: acc does not take a label")]
+    #[test_case("a:\na: nop +0", "line 2: duplicate label: a")]
+    #[test_case("1a: nop +0", "line 1: invalid label: 1a")]
+    fn test_assemble_error(source: &str, expected: &str) {
+        assert_eq!(assemble(source), Err(SimpleError::new(expected)));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program = vec![
+            Command::Nop(0),
+            Command::Acc(1),
+            Command::Jmp(4),
+            Command::Acc(3),
+            Command::Jmp(-3),
+            Command::Acc(-99),
+            Command::Acc(1),
+            Command::Jnz(-4),
+            Command::Jz(2),
+            Command::Jmp(-20),
+        ];
+
+        let source = disassemble(&program);
+        assert_eq!(
+            source,
+            "    nop +0\nl1:\n    acc +1\n    jmp l6\nl3:\n    acc +3\n    jmp l1\n    acc -99\n\
+             l6:\n    acc +1\n    jnz l3\n    jz l10\n    jmp -20\nl10:\n"
+        );
+        assert_eq!(assemble(&source), Ok(program));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::Command;
+use std::collections::HashSet;
+use std::fmt;
+
+// Control flow graph of a program. The nodes are the command indices plus
+// one extra node (`exit`, equal to the program length) for termination.
+pub struct ControlFlowGraph<'a> {
+    program: &'a [Command],
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    // (command, target) for every jump leaving the program
+    out_of_bounds: Vec<(usize, i64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    // commands no execution can ever get to
+    pub unreachable: Vec<usize>,
+    // (command, target) for every jump leaving the program
+    pub out_of_bounds: Vec<(usize, i64)>,
+    // reachable groups of commands which, once entered, never terminate
+    pub infinite_loops: Vec<Vec<usize>>,
+    // commands executed by every run that terminates. Empty if the
+    // program can't terminate at all.
+    pub always_executed: Vec<usize>,
+}
+
+// every node reachable from `start` along `edges`
+fn search(start: usize, edges: &[Vec<usize>]) -> Vec<bool> {
+    let mut found = vec![false; edges.len()];
+    found[start] = true;
+    let mut todo = vec![start];
+
+    while let Some(current) = todo.pop() {
+        for next in edges[current].iter() {
+            if !found[*next] {
+                found[*next] = true;
+                todo.push(*next);
+            }
+        }
+    }
+
+    found
+}
+
+// nodes in the order depth first search finishes them, only following
+// edges between nodes in `include`
+fn postorder(starts: &[usize], edges: &[Vec<usize>], include: &[bool]) -> Vec<usize> {
+    let mut visited = vec![false; edges.len()];
+    let mut result = Vec::new();
+
+    for &start in starts {
+        if visited[start] || !include[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
+
+        while let Some(&(current, edge)) = stack.last() {
+            match edges[current].get(edge) {
+                Some(&next) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    if include[next] && !visited[next] {
+                        visited[next] = true;
+                        stack.push((next, 0));
+                    }
+                }
+                None => {
+                    stack.pop();
+                    result.push(current);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+impl<'a> ControlFlowGraph<'a> {
+    pub fn new(program: &'a [Command]) -> Self {
+        let exit = program.len();
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); exit + 1];
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); exit + 1];
+        let mut out_of_bounds = Vec::new();
+
+        for (index, command) in program.iter().enumerate() {
+            let pc = index as i64;
+            let mut targets: Vec<Option<i64>> = Vec::new();
+            if command.is_conditional() {
+                // both ways are possible, without running we don't know
+                // what's in the accumulator
+                targets.push(pc.checked_add(1));
+                targets.push(pc.checked_add(command.value()));
+            } else {
+                targets.push(command.next(pc, 0));
+            }
+
+            for target in targets {
+                match target {
+                    Some(t) if t >= 0 && t <= exit as i64 => {
+                        let t = t as usize;
+                        if !successors[index].contains(&t) {
+                            successors[index].push(t);
+                            predecessors[t].push(index);
+                        }
+                    }
+                    _ => out_of_bounds.push((
+                        index,
+                        target.unwrap_or(if command.value() < 0 {
+                            i64::MIN
+                        } else {
+                            i64::MAX
+                        }),
+                    )),
+                }
+            }
+        }
+
+        ControlFlowGraph {
+            program,
+            successors,
+            predecessors,
+            out_of_bounds,
+        }
+    }
+
+    pub fn exit(&self) -> usize {
+        self.program.len()
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[node]
+    }
+
+    pub fn reachable(&self) -> Vec<bool> {
+        search(0, &self.successors)
+    }
+
+    // nodes from which the exit can be reached
+    pub fn can_terminate(&self) -> Vec<bool> {
+        search(self.exit(), &self.predecessors)
+    }
+
+    pub fn out_of_bounds(&self) -> &[(usize, i64)] {
+        &self.out_of_bounds
+    }
+
+    // strongly connected components (Kosaraju) of the reachable commands
+    // which can't reach the exit, only the ones which actually loop.
+    fn infinite_loops(&self, reachable: &[bool], can_terminate: &[bool]) -> Vec<Vec<usize>> {
+        let exit = self.exit();
+        let stuck: Vec<bool> = (0..=exit)
+            .map(|node| node != exit && reachable[node] && !can_terminate[node])
+            .collect();
+
+        let all: Vec<usize> = (0..exit).collect();
+        let order = postorder(&all, &self.successors, &stuck);
+
+        // second pass over the reversed graph, every search collects one
+        // component out of the nodes not assigned to another one yet
+        let mut remaining = stuck;
+        let mut result = Vec::new();
+        for &start in order.iter().rev() {
+            if !remaining[start] {
+                continue;
+            }
+
+            remaining[start] = false;
+            let mut component = vec![start];
+            let mut todo = vec![start];
+            while let Some(current) = todo.pop() {
+                for &previous in self.predecessors[current].iter() {
+                    if remaining[previous] {
+                        remaining[previous] = false;
+                        component.push(previous);
+                        todo.push(previous);
+                    }
+                }
+            }
+            component.sort_unstable();
+
+            if component.len() > 1 || self.successors[start].contains(&start) {
+                result.push(component);
+            }
+        }
+
+        result.sort();
+        result
+    }
+
+    // The commands on every path from the start to the exit are the
+    // dominators of the exit, computed with the algorithm from Cooper,
+    // Harvey & Kennedy ("A Simple, Fast Dominance Algorithm").
+    fn always_executed(&self, reachable: &[bool], can_terminate: &[bool]) -> Vec<usize> {
+        let exit = self.exit();
+        let include: Vec<bool> = (0..=exit)
+            .map(|node| reachable[node] && can_terminate[node])
+            .collect();
+        if !include[0] {
+            return Vec::new();
+        }
+
+        let order = postorder(&[0], &self.successors, &include);
+        let mut position = vec![usize::MAX; exit + 1];
+        for (i, node) in order.iter().enumerate() {
+            position[*node] = i;
+        }
+
+        let mut idom: Vec<Option<usize>> = vec![None; exit + 1];
+        idom[0] = Some(0);
+
+        let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while position[a] < position[b] {
+                    a = idom[a].unwrap();
+                }
+                while position[b] < position[a] {
+                    b = idom[b].unwrap();
+                }
+            }
+            a
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in order.iter().rev().filter(|n| **n != 0) {
+                let mut new_idom: Option<usize> = None;
+                for &pred in self.predecessors[node].iter() {
+                    if !include[pred] || idom[pred].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(current) => intersect(&idom, pred, current),
+                    });
+                }
+                if new_idom.is_some() && idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut result = Vec::new();
+        let mut node = exit;
+        while node != 0 {
+            node = idom[node].unwrap();
+            result.push(node);
+        }
+        result.sort_unstable();
+        result
+    }
+
+    pub fn analyze(&self) -> Analysis {
+        let reachable = self.reachable();
+        let can_terminate = self.can_terminate();
+
+        Analysis {
+            unreachable: (0..self.exit()).filter(|n| !reachable[*n]).collect(),
+            out_of_bounds: self.out_of_bounds.clone(),
+            infinite_loops: self.infinite_loops(&reachable, &can_terminate),
+            always_executed: self.always_executed(&reachable, &can_terminate),
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let in_loop: HashSet<usize> = self
+            .analyze()
+            .infinite_loops
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Dot {
+            graph: self,
+            in_loop,
+        }
+        .to_string()
+    }
+}
+
+// the graph with the commands in `in_loop` marked red
+struct Dot<'g, 'a> {
+    graph: &'g ControlFlowGraph<'a>,
+    in_loop: HashSet<usize>,
+}
+
+impl fmt::Display for Dot<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let graph = self.graph;
+
+        writeln!(f, "digraph program {{")?;
+        for (index, command) in graph.program.iter().enumerate() {
+            writeln!(
+                f,
+                "    n{} [label=\"{}: {}\"{}];",
+                index,
+                index,
+                command,
+                if self.in_loop.contains(&index) {
+                    ", color=red"
+                } else {
+                    ""
+                }
+            )?;
+        }
+        writeln!(
+            f,
+            "    n{} [label=\"exit\", shape=doublecircle];",
+            graph.exit()
+        )?;
+        if !graph.out_of_bounds.is_empty() {
+            writeln!(f, "    out [label=\"out of bounds\", shape=octagon];")?;
+        }
+
+        for (index, successors) in graph.successors.iter().enumerate() {
+            for next in successors {
+                writeln!(f, "    n{} -> n{};", index, next)?;
+            }
+        }
+        for (index, _) in graph.out_of_bounds.iter() {
+            writeln!(f, "    n{} -> out;", index)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_program;
+
+    #[test]
+    fn test_analyze_looping() {
+        let program = example_program();
+        assert_eq!(
+            ControlFlowGraph::new(&program).analyze(),
+            Analysis {
+                unreachable: vec![5, 8],
+                out_of_bounds: vec![],
+                infinite_loops: vec![vec![1, 2, 3, 4, 6, 7]],
+                always_executed: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_analyze_terminating() {
+        let mut program = example_program();
+        program[7] = Command::Nop(-4);
+
+        let analysis = ControlFlowGraph::new(&program).analyze();
+        assert_eq!(analysis.infinite_loops, Vec::<Vec<usize>>::new());
+        assert_eq!(analysis.always_executed, vec![0, 1, 2, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_analyze_conditional() {
+        let program = vec![
+            Command::Jz(3),
+            Command::Acc(1),
+            Command::Jmp(3),
+            Command::Jnz(-3),
+            Command::Jmp(-20),
+            Command::Nop(0),
+        ];
+
+        assert_eq!(
+            ControlFlowGraph::new(&program).analyze(),
+            Analysis {
+                unreachable: vec![],
+                out_of_bounds: vec![(4, -16)],
+                infinite_loops: vec![],
+                always_executed: vec![0, 1, 2, 5],
+            }
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let program = vec![Command::Acc(1), Command::Jmp(-1), Command::Jmp(-5)];
+        assert_eq!(
+            ControlFlowGraph::new(&program).to_dot(),
+            [
+                "digraph program {",
+                "    n0 [label=\"0: acc +1\", color=red];",
+                "    n1 [label=\"1: jmp -1\", color=red];",
+                "    n2 [label=\"2: jmp -5\"];",
+                "    n3 [label=\"exit\", shape=doublecircle];",
+                "    out [label=\"out of bounds\", shape=octagon];",
+                "    n0 -> n1;",
+                "    n1 -> n0;",
+                "    n2 -> out;",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{Command, LoopDetector, Outcome, TraceStep, Vm};
+use simple_error::SimpleError;
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+commands:
+  s, step            execute one command
+  c, continue        run until a breakpoint, a loop or the end
+  b, break <pc>      stop before executing the command at <pc>
+  b, break acc <n>   stop when the accumulator becomes <n>
+  d, delete          remove all breakpoints
+  i, info            show pc, accumulator, steps and breakpoints
+  l, list            show the program around the pc
+  r, reset           start the program from the beginning
+  q, quit            leave the debugger";
+
+// why `continue` returned
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stop {
+    Breakpoint(i64),
+    AccumulatorBreakpoint(i64),
+    Halted(Outcome),
+}
+
+pub struct Debugger<'a> {
+    vm: Vm<'a>,
+    pc_breakpoints: BTreeSet<i64>,
+    acc_breakpoints: BTreeSet<i64>,
+    // every state since the start, kept across `continue`s and steps
+    seen: LoopDetector,
+    // the pc breakpoint the last `continue` stopped at
+    stopped_at: Option<i64>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a [Command]) -> Self {
+        Debugger::with_vm(Vm::new(program))
+    }
+
+    // debug the given machine, e.g. one with a different step limit
+    pub fn with_vm(vm: Vm<'a>) -> Self {
+        let program = vm.program();
+        Debugger {
+            vm,
+            pc_breakpoints: BTreeSet::new(),
+            acc_breakpoints: BTreeSet::new(),
+            seen: LoopDetector::new(program),
+            stopped_at: None,
+        }
+    }
+
+    pub fn vm(&self) -> &Vm<'a> {
+        &self.vm
+    }
+
+    pub fn break_at_pc(&mut self, pc: i64) {
+        self.pc_breakpoints.insert(pc);
+    }
+
+    pub fn break_at_accumulator(&mut self, value: i64) {
+        self.acc_breakpoints.insert(value);
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.pc_breakpoints.clear();
+        self.acc_breakpoints.clear();
+    }
+
+    pub fn reset(&mut self) {
+        let program = self.vm.program();
+        self.vm = Vm::new(program).with_step_limit(self.vm.step_limit());
+        self.seen = LoopDetector::new(program);
+        self.stopped_at = None;
+    }
+
+    // execute a single command, `None` if the program was already halted
+    pub fn step(&mut self) -> Option<TraceStep> {
+        if self.vm.halted().is_some() {
+            return None;
+        }
+        self.seen
+            .repeated(self.vm.pc() as usize, self.vm.accumulator());
+        self.stopped_at = None;
+        self.execute_current()
+    }
+
+    // execute the current command without remembering the state
+    fn execute_current(&mut self) -> Option<TraceStep> {
+        let command = self.vm.current()?.clone();
+        let pc = self.vm.pc();
+        let acc_before = self.vm.accumulator();
+
+        if let Some(Outcome::Overflow { .. }) = self.vm.step() {
+            return None;
+        }
+
+        Some(TraceStep {
+            pc,
+            command,
+            acc_before,
+            acc_after: self.vm.accumulator(),
+        })
+    }
+
+    pub fn cont(&mut self) -> Stop {
+        // only the breakpoint we are resuming from is skipped, once
+        let mut resuming = self.stopped_at.take();
+
+        loop {
+            if let Some(outcome) = self.vm.halted() {
+                return Stop::Halted(outcome);
+            }
+
+            let pc = self.vm.pc();
+            if self.pc_breakpoints.contains(&pc) && resuming != Some(pc) {
+                self.stopped_at = Some(pc);
+                return Stop::Breakpoint(pc);
+            }
+            resuming = None;
+
+            if self.seen.needs_step_limit() && self.vm.steps() >= self.vm.step_limit() {
+                return Stop::Halted(Outcome::StepLimit {
+                    acc: self.vm.accumulator(),
+                    pc: pc as usize,
+                });
+            }
+            if self.seen.repeated(pc as usize, self.vm.accumulator()) {
+                return Stop::Halted(Outcome::InfiniteLoop {
+                    acc: self.vm.accumulator(),
+                    pc: pc as usize,
+                });
+            }
+
+            if let Some(step) = self.execute_current() {
+                if step.acc_before != step.acc_after
+                    && self.acc_breakpoints.contains(&step.acc_after)
+                {
+                    return Stop::AccumulatorBreakpoint(step.acc_after);
+                }
+            }
+        }
+    }
+
+    fn info(&self) -> String {
+        let list = |set: &BTreeSet<i64>| {
+            set.iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        format!(
+            "pc {}, acc {}, steps {}\nbreakpoints pc: [{}], acc: [{}]",
+            self.vm.pc(),
+            self.vm.accumulator(),
+            self.vm.steps(),
+            list(&self.pc_breakpoints),
+            list(&self.acc_breakpoints),
+        )
+    }
+
+    fn list(&self) -> String {
+        let pc = self.vm.pc();
+        let program = self.vm.program();
+
+        let from = pc.saturating_sub(3).max(0);
+        let to = pc.saturating_add(4).min(program.len() as i64);
+
+        (from..to)
+            .map(|line| {
+                format!(
+                    "{} {:>5}: {}",
+                    if line == pc { "=>" } else { "  " },
+                    line,
+                    program[line as usize]
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // run one debugger command line, returns the text to show
+    pub fn execute(&mut self, line: &str) -> Result<String, SimpleError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let parse = |s: &str| {
+            s.parse::<i64>()
+                .map_err(|_| SimpleError::new(format!("invalid number: {}", s)))
+        };
+
+        match words.as_slice() {
+            ["s"] | ["step"] => Ok(match self.step() {
+                Some(step) => step.to_string(),
+                None => format!("{:?}", self.vm.halted()),
+            }),
+            ["c"] | ["continue"] => Ok(match self.cont() {
+                Stop::Breakpoint(pc) => format!("breakpoint at pc {}", pc),
+                Stop::AccumulatorBreakpoint(acc) => format!("breakpoint at acc {}", acc),
+                Stop::Halted(outcome) => format!("{:?}", outcome),
+            }),
+            ["b", "acc", value] | ["break", "acc", value] => {
+                self.break_at_accumulator(parse(value)?);
+                Ok(format!("breakpoint at acc {}", value))
+            }
+            ["b", pc] | ["break", pc] => {
+                self.break_at_pc(parse(pc)?);
+                Ok(format!("breakpoint at pc {}", pc))
+            }
+            ["d"] | ["delete"] => {
+                self.clear_breakpoints();
+                Ok("all breakpoints deleted".to_string())
+            }
+            ["i"] | ["info"] => Ok(self.info()),
+            ["l"] | ["list"] => Ok(self.list()),
+            ["r"] | ["reset"] => {
+                self.reset();
+                Ok(self.info())
+            }
+            ["h"] | ["help"] => Ok(HELP.to_string()),
+            _ => Err(SimpleError::new(format!("unknown command: {}", line))),
+        }
+    }
+
+    pub fn repl(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "{}", HELP)?;
+        write!(output, "> ")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            let line = line?;
+            match line.trim() {
+                "q" | "quit" => break,
+                "" => {}
+                command => match self.execute(command) {
+                    Ok(text) => writeln!(output, "{}", text)?,
+                    Err(err) => writeln!(output, "error: {}", err)?,
+                },
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_program;
+
+    #[test]
+    fn test_continue_until_loop() {
+        let program = example_program();
+        let mut debugger = Debugger::new(&program);
+
+        assert_eq!(
+            debugger.cont(),
+            Stop::Halted(Outcome::InfiniteLoop { acc: 5, pc: 1 })
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let program = example_program();
+        let mut debugger = Debugger::new(&program);
+        debugger.break_at_pc(6);
+        debugger.break_at_accumulator(5);
+
+        assert_eq!(debugger.cont(), Stop::Breakpoint(6));
+        assert_eq!(debugger.vm().accumulator(), 1);
+
+        assert_eq!(debugger.cont(), Stop::AccumulatorBreakpoint(5));
+        assert_eq!(debugger.vm().pc(), 4);
+    }
+
+    #[test]
+    fn test_breakpoint_at_start() {
+        let program = example_program();
+        let mut debugger = Debugger::new(&program);
+        debugger.break_at_pc(0);
+
+        assert_eq!(debugger.cont(), Stop::Breakpoint(0));
+        assert_eq!(debugger.vm().steps(), 0);
+        assert_eq!(
+            debugger.cont(),
+            Stop::Halted(Outcome::InfiniteLoop { acc: 5, pc: 1 })
+        );
+    }
+
+    #[test]
+    fn test_loop_across_continues() {
+        let program = example_program();
+        let mut debugger = Debugger::new(&program);
+        debugger.break_at_pc(1);
+
+        assert_eq!(debugger.cont(), Stop::Breakpoint(1));
+        assert_eq!(debugger.cont(), Stop::Breakpoint(1));
+        assert_eq!(debugger.vm().accumulator(), 5);
+        assert_eq!(
+            debugger.cont(),
+            Stop::Halted(Outcome::InfiniteLoop { acc: 5, pc: 1 })
+        );
+
+        debugger.reset();
+        debugger.step();
+        debugger.step();
+        assert_eq!(debugger.cont(), Stop::Breakpoint(1));
+        assert_eq!(
+            debugger.cont(),
+            Stop::Halted(Outcome::InfiniteLoop { acc: 5, pc: 1 })
+        );
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let program = vec![Command::Acc(1), Command::Jnz(-1)];
+        let mut debugger = Debugger::with_vm(Vm::new(&program).with_step_limit(10));
+
+        assert_eq!(
+            debugger.cont(),
+            Stop::Halted(Outcome::StepLimit { acc: 5, pc: 0 })
+        );
+        debugger.reset();
+        assert_eq!(debugger.vm().step_limit(), 10);
+
+        // without conditional jumps the limit doesn't apply
+        let program = vec![Command::Nop(0); 20];
+        let mut debugger = Debugger::with_vm(Vm::new(&program).with_step_limit(10));
+        assert_eq!(debugger.cont(), Stop::Halted(Outcome::Terminated(0)));
+    }
+
+    #[test]
+    fn test_execute() {
+        let program = example_program();
+        let mut debugger = Debugger::new(&program);
+
+        assert_eq!(
+            debugger.execute("step"),
+            Ok("    0: nop +0     acc 0 -> 0".to_string())
+        );
+        assert_eq!(
+            debugger.execute("b 4"),
+            Ok("breakpoint at pc 4".to_string())
+        );
+        assert_eq!(debugger.execute("c"), Ok("breakpoint at pc 4".to_string()));
+        assert_eq!(
+            debugger.execute("info"),
+            Ok("pc 4, acc 5, steps 6\nbreakpoints pc: [4], acc: []".to_string())
+        );
+        assert_eq!(
+            debugger.execute("b x"),
+            Err(SimpleError::new("invalid number: x"))
+        );
+        assert_eq!(
+            debugger.execute("jump"),
+            Err(SimpleError::new("unknown command: jump"))
+        );
+    }
+
+    #[test]
+    fn test_repl() {
+        let program = example_program();
+        let mut debugger = Debugger::new(&program);
+        let mut output: Vec<u8> = Vec::new();
+
+        debugger
+            .repl("s\ns\nquit\ns\n".as_bytes(), &mut output)
+            .unwrap();
+
+        assert_eq!(debugger.vm().steps(), 2);
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("    1: acc +1     acc 0 -> 1"));
+    }
+}
@@ -0,0 +1,548 @@
+use simple_error::SimpleError;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+
+mod asm;
+mod cfg;
+mod debugger;
+mod repair;
+pub use asm::{assemble, disassemble};
+pub use cfg::{Analysis, ControlFlowGraph};
+pub use debugger::{Debugger, Stop};
+pub use repair::{repair, Repair};
+
+// To add an opcode, add a variant here and teach `new`, `op`, `value`
+// and `next` about it. Parsing, printing and the assembler use those.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Acc(i64),
+    Jmp(i64),
+    Nop(i64),
+    // multiply the accumulator
+    Mul(i64),
+    // jump if the accumulator is zero
+    Jz(i64),
+    // jump if the accumulator is not zero
+    Jnz(i64),
+}
+
+impl Command {
+    pub fn new(op: &str, value: i64) -> Option<Command> {
+        match op {
+            "acc" => Some(Command::Acc(value)),
+            "jmp" => Some(Command::Jmp(value)),
+            "nop" => Some(Command::Nop(value)),
+            "mul" => Some(Command::Mul(value)),
+            "jz" => Some(Command::Jz(value)),
+            "jnz" => Some(Command::Jnz(value)),
+            _ => None,
+        }
+    }
+
+    pub fn op(&self) -> &'static str {
+        match self {
+            Command::Acc(_) => "acc",
+            Command::Jmp(_) => "jmp",
+            Command::Nop(_) => "nop",
+            Command::Mul(_) => "mul",
+            Command::Jz(_) => "jz",
+            Command::Jnz(_) => "jnz",
+        }
+    }
+
+    pub fn value(&self) -> i64 {
+        match self {
+            Command::Acc(value)
+            | Command::Jmp(value)
+            | Command::Nop(value)
+            | Command::Mul(value)
+            | Command::Jz(value)
+            | Command::Jnz(value) => *value,
+        }
+    }
+
+    // the value is a relative offset to another command
+    pub fn is_jump(&self) -> bool {
+        matches!(self, Command::Jmp(_) | Command::Jz(_) | Command::Jnz(_))
+    }
+
+    // where the command continues depends on the accumulator
+    pub fn is_conditional(&self) -> bool {
+        matches!(self, Command::Jz(_) | Command::Jnz(_))
+    }
+
+    // the pc after executing this command at `pc` with the accumulator
+    // at `acc`, `None` on overflow
+    pub fn next(&self, pc: i64, acc: i64) -> Option<i64> {
+        match self {
+            Command::Jmp(value) => pc.checked_add(*value),
+            Command::Jz(value) if acc == 0 => pc.checked_add(*value),
+            Command::Jnz(value) if acc != 0 => pc.checked_add(*value),
+            _ => pc.checked_add(1),
+        }
+    }
+
+    // the command with `jmp` and `nop` swapped
+    pub fn flipped(&self) -> Option<Command> {
+        match self {
+            Command::Jmp(value) => Some(Command::Nop(*value)),
+            Command::Nop(value) => Some(Command::Jmp(*value)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // `pad` so width and alignment work for commands too
+        f.pad(&format!("{} {:+}", self.op(), self.value()))
+    }
+}
+
+// one command like `acc +1` per line, empty lines are skipped
+pub fn read_commands(lines: &[String]) -> Result<Vec<Command>, SimpleError> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let error = |msg: String| SimpleError::new(format!("line {}: {}", i + 1, msg));
+
+            let (op, value_str) = line
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| error(format!("expected \"<op> <value>\": {}", line.trim())))?;
+            let value: i64 = value_str
+                .parse()
+                .map_err(|_| error(format!("invalid value: {}", value_str)))?;
+
+            Command::new(op, value).ok_or_else(|| error(format!("unknown command: {}", op)))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    // pc reached the position right after the last command
+    Terminated(i64),
+    // the command at `pc` was about to be executed a second time
+    InfiniteLoop { acc: i64, pc: usize },
+    // a jump left the program
+    OutOfBounds { pc: i64 },
+    // the command at `pc` would overflow the accumulator
+    Overflow { acc: i64, pc: usize },
+    // ran for the step limit without halting or repeating a state
+    StepLimit { acc: i64, pc: usize },
+}
+
+// With conditional jumps a program can run forever without ever repeating
+// a state (`acc +1; jnz -1`), every state is kept to detect loops, so
+// running is stopped after this many steps. Programs without them always
+// halt or repeat a pc, so they run without a limit.
+pub const STEP_LIMIT: usize = 1_000_000;
+
+// Without conditional jumps the accumulator has no influence on where a
+// program goes, so visiting a pc twice already means it loops forever.
+// With them, only seeing the same pc with the same accumulator does.
+pub(crate) enum LoopDetector {
+    Pc(Vec<bool>),
+    State(HashSet<(usize, i64)>),
+}
+
+impl LoopDetector {
+    pub(crate) fn new(program: &[Command]) -> Self {
+        if program.iter().any(|command| command.is_conditional()) {
+            LoopDetector::State(HashSet::new())
+        } else {
+            LoopDetector::Pc(vec![false; program.len()])
+        }
+    }
+
+    // whether the program can run forever without repeating a state, so
+    // the step limit applies
+    pub(crate) fn needs_step_limit(&self) -> bool {
+        matches!(self, LoopDetector::State(_))
+    }
+
+    // remembers the state, returns whether it was seen before
+    pub(crate) fn repeated(&mut self, pc: usize, acc: i64) -> bool {
+        match self {
+            LoopDetector::Pc(visited) => std::mem::replace(&mut visited[pc], true),
+            LoopDetector::State(seen) => !seen.insert((pc, acc)),
+        }
+    }
+}
+
+// one executed command
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    pub pc: i64,
+    pub command: Command,
+    pub acc_before: i64,
+    pub acc_after: i64,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>5}: {:<10} acc {} -> {}",
+            self.pc, self.command, self.acc_before, self.acc_after
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vm<'a> {
+    program: &'a [Command],
+    pc: i64,
+    accumulator: i64,
+    steps: usize,
+    step_limit: usize,
+    overflowed: bool,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(program: &'a [Command]) -> Self {
+        Vm {
+            program,
+            pc: 0,
+            accumulator: 0,
+            steps: 0,
+            step_limit: STEP_LIMIT,
+            overflowed: false,
+        }
+    }
+
+    pub fn with_step_limit(mut self, step_limit: usize) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
+
+    pub fn program(&self) -> &'a [Command] {
+        self.program
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    pub fn accumulator(&self) -> i64 {
+        self.accumulator
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn step_limit(&self) -> usize {
+        self.step_limit
+    }
+
+    // the command at the current pc, `None` when the program is halted
+    pub fn current(&self) -> Option<&'a Command> {
+        usize::try_from(self.pc)
+            .ok()
+            .and_then(|pc| self.program.get(pc))
+    }
+
+    // `None` while there are commands left to execute
+    pub fn halted(&self) -> Option<Outcome> {
+        if self.overflowed {
+            Some(Outcome::Overflow {
+                acc: self.accumulator,
+                pc: self.pc as usize,
+            })
+        } else if self.pc == self.program.len() as i64 {
+            Some(Outcome::Terminated(self.accumulator))
+        } else if self.current().is_none() {
+            Some(Outcome::OutOfBounds { pc: self.pc })
+        } else {
+            None
+        }
+    }
+
+    // execute one command. Returns the outcome when the program is halted
+    // afterwards (or already was before).
+    pub fn step(&mut self) -> Option<Outcome> {
+        if let Some(outcome) = self.halted() {
+            return Some(outcome);
+        }
+        let command = &self.program[self.pc as usize];
+
+        let accumulator = match command {
+            Command::Acc(value) => self.accumulator.checked_add(*value),
+            Command::Mul(value) => self.accumulator.checked_mul(*value),
+            _ => Some(self.accumulator),
+        };
+        match accumulator {
+            Some(accumulator) => self.accumulator = accumulator,
+            None => {
+                // stay in front of the command, like the other halts
+                self.overflowed = true;
+                return self.halted();
+            }
+        }
+
+        // a jump beyond what fits in the pc is out of bounds too
+        self.pc = command
+            .next(self.pc, self.accumulator)
+            .unwrap_or(if command.value() < 0 {
+                i64::MIN
+            } else {
+                i64::MAX
+            });
+        self.steps += 1;
+
+        self.halted()
+    }
+
+    pub fn run(&mut self) -> Outcome {
+        self.run_with(&mut |_| {})
+    }
+
+    // like `run`, but records every executed command
+    pub fn run_traced(&mut self) -> (Outcome, Vec<TraceStep>) {
+        let mut trace: Vec<TraceStep> = Vec::new();
+        let outcome = self.run_with(&mut |step| trace.push(step));
+        (outcome, trace)
+    }
+
+    fn run_with(&mut self, on_step: &mut dyn FnMut(TraceStep)) -> Outcome {
+        let mut seen = LoopDetector::new(self.program);
+
+        loop {
+            if let Some(outcome) = self.halted() {
+                return outcome;
+            }
+
+            let pc = self.pc as usize;
+            if seen.needs_step_limit() && self.steps >= self.step_limit {
+                return Outcome::StepLimit {
+                    acc: self.accumulator,
+                    pc,
+                };
+            }
+            if seen.repeated(pc, self.accumulator) {
+                return Outcome::InfiniteLoop {
+                    acc: self.accumulator,
+                    pc,
+                };
+            }
+
+            let acc_before = self.accumulator;
+            self.step();
+            if self.overflowed {
+                continue;
+            }
+            on_step(TraceStep {
+                pc: pc as i64,
+                command: self.program[pc].clone(),
+                acc_before,
+                acc_after: self.accumulator,
+            });
+        }
+    }
+}
+
+// the example program from the puzzle
+#[cfg(test)]
+pub(crate) fn example_program() -> Vec<Command> {
+    vec![
+        Command::Nop(0),
+        Command::Acc(1),
+        Command::Jmp(4),
+        Command::Acc(3),
+        Command::Jmp(-3),
+        Command::Acc(-99),
+        Command::Acc(1),
+        Command::Jmp(-4),
+        Command::Acc(6),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &[&str]) -> Vec<String> {
+        input.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unknown_commands() {
+        assert_eq!(
+            read_commands(&lines(&["asd +0"])),
+            Err(SimpleError::new("line 1: unknown command: asd"))
+        );
+        assert_eq!(
+            read_commands(&lines(&["acc +a"])),
+            Err(SimpleError::new("line 1: invalid value: +a"))
+        );
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        assert_eq!(
+            read_commands(&lines(&["nop +0", "", "nop"])),
+            Err(SimpleError::new("line 3: expected \"<op> <value>\": nop"))
+        );
+        assert_eq!(
+            read_commands(&lines(&["acc+1"])),
+            Err(SimpleError::new("line 1: expected \"<op> <value>\": acc+1"))
+        );
+        assert_eq!(
+            read_commands(&lines(&["acc +1", "", "jmp -1", ""])),
+            Ok(vec![Command::Acc(1), Command::Jmp(-1)])
+        );
+    }
+
+    #[test]
+    fn test_read_commands() {
+        let commands = read_commands(&lines(&["nop +0", "acc +1", "jmp -4"])).unwrap();
+        assert_eq!(commands[0], Command::Nop(0));
+        assert_eq!(commands[1], Command::Acc(1));
+        assert_eq!(commands[2], Command::Jmp(-4));
+    }
+
+    #[test]
+    fn test_read_extended_commands() {
+        let commands = read_commands(&lines(&["mul +2", "jz -1", "jnz +3"])).unwrap();
+        assert_eq!(
+            commands,
+            vec![Command::Mul(2), Command::Jz(-1), Command::Jnz(3)]
+        );
+        assert_eq!(commands[1].to_string(), "jz -1");
+    }
+
+    #[test]
+    fn test_run_extended_commands() {
+        // acc = 3; while acc != 0 { acc -= 1 }; acc = acc * 5 + 2
+        let commands = vec![
+            Command::Acc(3),
+            Command::Jz(3),
+            Command::Acc(-1),
+            Command::Jmp(-2),
+            Command::Acc(2),
+            Command::Mul(5),
+        ];
+        assert_eq!(Vm::new(&commands).run(), Outcome::Terminated(10));
+    }
+
+    #[test]
+    fn test_run_overflow() {
+        // doubles the accumulator until it doesn't fit anymore
+        let commands = vec![Command::Acc(1), Command::Mul(2), Command::Jnz(-1)];
+        let mut vm = Vm::new(&commands);
+
+        assert_eq!(
+            vm.run(),
+            Outcome::Overflow {
+                acc: 1 << 62,
+                pc: 1
+            }
+        );
+        assert_eq!(vm.steps(), 1 + 2 * 62);
+        assert_eq!(
+            vm.step(),
+            Some(Outcome::Overflow {
+                acc: 1 << 62,
+                pc: 1
+            })
+        );
+        assert_eq!(vm.steps(), 1 + 2 * 62);
+
+        let commands = vec![Command::Acc(i64::MIN), Command::Acc(-1)];
+        assert_eq!(
+            Vm::new(&commands).run(),
+            Outcome::Overflow {
+                acc: i64::MIN,
+                pc: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_run_step_limit() {
+        // counts up forever, the state never repeats
+        let commands = vec![Command::Acc(1), Command::Jnz(-1)];
+
+        assert_eq!(
+            Vm::new(&commands).with_step_limit(1000).run(),
+            Outcome::StepLimit { acc: 500, pc: 0 }
+        );
+        assert_eq!(
+            Vm::new(&commands).run(),
+            Outcome::StepLimit {
+                acc: STEP_LIMIT as i64 / 2,
+                pc: 0
+            }
+        );
+
+        // without conditional jumps the limit doesn't apply
+        let commands = vec![Command::Nop(0); STEP_LIMIT + 1];
+        assert_eq!(Vm::new(&commands).run(), Outcome::Terminated(0));
+        let commands = vec![Command::Acc(1), Command::Acc(2), Command::Jmp(-2)];
+        assert_eq!(
+            Vm::new(&commands).with_step_limit(1).run(),
+            Outcome::InfiniteLoop { acc: 3, pc: 0 }
+        );
+    }
+
+    #[test]
+    fn test_step() {
+        let commands = vec![Command::Acc(3), Command::Jmp(2), Command::Nop(0)];
+        let mut vm = Vm::new(&commands);
+
+        assert_eq!(vm.step(), None);
+        assert_eq!((vm.pc(), vm.accumulator(), vm.steps()), (1, 3, 1));
+
+        assert_eq!(vm.step(), Some(Outcome::Terminated(3)));
+        assert_eq!((vm.pc(), vm.accumulator(), vm.steps()), (3, 3, 2));
+
+        // halted machines don't move anymore
+        assert_eq!(vm.step(), Some(Outcome::Terminated(3)));
+        assert_eq!(vm.steps(), 2);
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(
+            Vm::new(&example_program()).run(),
+            Outcome::InfiniteLoop { acc: 5, pc: 1 }
+        );
+    }
+
+    #[test]
+    fn test_run_traced() {
+        let commands = vec![Command::Nop(0), Command::Acc(2), Command::Jmp(-1)];
+        let (outcome, trace) = Vm::new(&commands).run_traced();
+
+        assert_eq!(outcome, Outcome::InfiniteLoop { acc: 2, pc: 1 });
+        assert_eq!(
+            trace
+                .iter()
+                .map(|step| step.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "    0: nop +0     acc 0 -> 0",
+                "    1: acc +2     acc 0 -> 2",
+                "    2: jmp -1     acc 2 -> 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_out_of_bounds() {
+        let commands = vec![Command::Acc(1), Command::Jmp(-2)];
+        assert_eq!(Vm::new(&commands).run(), Outcome::OutOfBounds { pc: -1 });
+
+        // 1 + i64::MAX overflows
+        let commands = vec![Command::Nop(0), Command::Jmp(i64::MAX)];
+        assert_eq!(
+            Vm::new(&commands).run(),
+            Outcome::OutOfBounds { pc: i64::MAX }
+        );
+    }
+}
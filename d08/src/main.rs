@@ -1,18 +1,26 @@
-#![feature(str_split_once)]
-
+use d08::{
+    assemble, disassemble, read_commands, repair, Command, ControlFlowGraph, Debugger, Outcome, Vm,
+};
 use simple_error::SimpleError;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-#[derive(Debug, Clone, PartialEq)]
-enum Command {
-    Acc(i64),
-    Jmp(i64),
-    Nop(i64),
-}
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
 
 fn main() {
+    // `d08 asm <file>` prints the assembled program in the puzzle format
+    if env::args().nth(1).as_deref() == Some("asm") {
+        let path = env::args().nth(2).expect("missing source file");
+        match assemble(&fs::read_to_string(path).unwrap()) {
+            Ok(commands) => {
+                for command in commands {
+                    println!("{}", command);
+                }
+            }
+            Err(err) => eprintln!("{}", err),
+        }
+        return;
+    }
+
     let file = File::open("input.txt").unwrap();
 
     let input: Vec<String> = BufReader::new(file)
@@ -21,77 +29,46 @@ fn main() {
         .collect();
 
     if let Ok(commands) = read_commands(&input) {
-        println!("part 1:{:?}", run(&commands));
-        println!("part 2:{:?}", try_fix(&commands));
-    }
-}
-
-fn read_commands(lines: &Vec<String>) -> Result<Vec<Command>, SimpleError> {
-    lines
-        .iter()
-        .filter_map(|line| line.split_once(" "))
-        .map(|(op, value_str)| (op.to_owned(), value_str.parse::<i64>().unwrap()))
-        .map(|(op, value)| match &op[..] {
-            "acc" => Ok(Command::Acc(value)),
-            "jmp" => Ok(Command::Jmp(value)),
-            "nop" => Ok(Command::Nop(value)),
-            _ => Err(SimpleError::new(format!("unknown command: {}", op))),
-        })
-        .collect()
-}
-
-fn run(commands: &Vec<Command>) -> Result<i64, SimpleError> {
-    let mut accumulator: i64 = 0;
-    let mut steps_done: HashSet<usize> = HashSet::new();
-
-    let mut i: usize = 0;
-    loop {
-        if steps_done.contains(&i) {
-            // jump to an already done location is an error
-            return Err(SimpleError::new(accumulator.to_string()));
-        }
-        if i == commands.len() {
-            // successful exit position is 1 after the last element
-            return Ok(accumulator);
-        }
-
-        steps_done.insert(i);
-        match commands[i] {
-            Command::Acc(value) => {
-                accumulator += value;
-                i += 1;
+        match env::args().nth(1).as_deref() {
+            Some("trace") => {
+                let (outcome, trace) = Vm::new(&commands).run_traced();
+                for step in trace {
+                    println!("{}", step);
+                }
+                println!("{:?}", outcome);
+                return;
             }
-            Command::Jmp(value) => {
-                i = (i as i64 + value) as usize;
+            Some("analyze") => {
+                println!("{:#?}", ControlFlowGraph::new(&commands).analyze());
+                return;
             }
-            Command::Nop(_) => {
-                i += 1;
+            Some("cfg") => {
+                print!("{}", ControlFlowGraph::new(&commands).to_dot());
+                return;
             }
-        }
-    }
-}
-
-fn try_fix(commands: &Vec<Command>) -> Result<i64, SimpleError> {
-    for line_to_try in 0..commands.len() {
-        let mut test_data: Vec<Command> = commands.clone();
-
-        match test_data[line_to_try] {
-            Command::Nop(value) => {
-                test_data[line_to_try] = Command::Jmp(value);
-                if let Ok(result) = run(&test_data) {
-                    return Ok(result);
-                }
+            Some("disasm") => {
+                print!("{}", disassemble(&commands));
+                return;
             }
-            Command::Jmp(value) => {
-                test_data[line_to_try] = Command::Nop(value);
-                if let Ok(result) = run(&test_data) {
-                    return Ok(result);
-                }
+            Some("debug") => {
+                Debugger::new(&commands)
+                    .repl(io::stdin().lock(), &mut io::stdout())
+                    .unwrap();
+                return;
             }
             _ => {}
         }
+
+        println!("part 1:{:?}", run(&commands));
+        println!("part 2:{:?}", repair(&commands).map(|r| r.acc));
+    }
+}
+
+fn run(commands: &[Command]) -> Result<i64, SimpleError> {
+    match Vm::new(commands).run() {
+        Outcome::InfiniteLoop { acc, .. } => Ok(acc),
+        outcome => Err(SimpleError::new(format!("no loop found: {:?}", outcome))),
     }
-    Err(SimpleError::new("nothing found"))
 }
 
 #[cfg(test)]
@@ -99,7 +76,7 @@ mod tests {
     use super::*;
 
     fn test_data() -> Vec<String> {
-        vec![
+        [
             "nop +0", "acc +1", "jmp +4", "acc +3", "jmp -3", "acc -99", "acc +1", "jmp -4",
             "acc +6",
         ]
@@ -108,41 +85,17 @@ mod tests {
         .collect()
     }
 
-    #[test]
-    fn test_unknown_commands() {
-        let td: Vec<String> = vec!["asd +0".to_string()];
-
-        assert_eq!(
-            read_commands(&td),
-            Err(SimpleError::new("unknown command: asd"))
-        );
-    }
-
-    #[test]
-    fn test_read_commands() {
-        let td: Vec<String> = vec![
-            "nop +0".to_string(),
-            "acc +1".to_string(),
-            "jmp -4".to_string(),
-        ];
-
-        let commands = read_commands(&td).unwrap();
-        assert_eq!(commands[0], Command::Nop(0));
-        assert_eq!(commands[1], Command::Acc(1));
-        assert_eq!(commands[2], Command::Jmp(-4));
-    }
-
     #[test]
     fn it_works() {
         let td = test_data();
         let commands = read_commands(&td).unwrap();
-        assert_eq!(run(&commands), Err(SimpleError::new("5")));
+        assert_eq!(run(&commands), Ok(5));
     }
 
     #[test]
-    fn test_try_fix() {
+    fn test_repair() {
         let td = test_data();
         let commands = read_commands(&td).unwrap();
-        assert_eq!(try_fix(&commands), Ok(8));
+        assert_eq!(repair(&commands).map(|r| r.acc), Ok(8));
     }
 }
@@ -0,0 +1,117 @@
+use crate::{Command, ControlFlowGraph, Outcome, Vm};
+use simple_error::SimpleError;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    // index of the changed command in the program
+    pub line: usize,
+    pub original: Command,
+    pub replacement: Command,
+    // accumulator after the repaired program terminated
+    pub acc: i64,
+}
+
+// index of the next command, `program.len()` meaning termination.
+// `None` when the command jumps out of the program.
+fn successor(program: &[Command], line: usize, command: &Command) -> Option<usize> {
+    // only called for programs without conditional jumps, so the
+    // accumulator doesn't matter
+    command
+        .next(line as i64, 0)
+        .and_then(|next| usize::try_from(next).ok())
+        .filter(|next| *next <= program.len())
+}
+
+// Finds the single `jmp` <-> `nop` flip which makes the program terminate.
+//
+// Instead of trying every flip this builds the control flow graph once,
+// marks every command from which the unchanged program would terminate
+// and then looks for a command on the looping path whose flipped version
+// continues at one of those.
+pub fn repair(program: &[Command]) -> Result<Repair, SimpleError> {
+    if program.iter().any(|command| command.is_conditional()) {
+        return Err(SimpleError::new(
+            "repair does not support conditional jumps",
+        ));
+    }
+
+    let terminates = ControlFlowGraph::new(program).can_terminate();
+
+    if terminates[0] {
+        return Err(SimpleError::new("program terminates without repair"));
+    }
+
+    // walk the original program until it loops, every command on the way
+    // is a candidate.
+    let mut visited = vec![false; program.len()];
+    let mut line: usize = 0;
+    while line < program.len() && !visited[line] {
+        visited[line] = true;
+        let command = &program[line];
+
+        if let Some(replacement) = command.flipped() {
+            if let Some(next) = successor(program, line, &replacement) {
+                if terminates[next] {
+                    let mut fixed = program.to_vec();
+                    fixed[line] = replacement.clone();
+
+                    return match Vm::new(&fixed).run() {
+                        Outcome::Terminated(acc) => Ok(Repair {
+                            line,
+                            original: command.clone(),
+                            replacement,
+                            acc,
+                        }),
+                        outcome => Err(SimpleError::new(format!(
+                            "repaired program did not terminate: {:?}",
+                            outcome
+                        ))),
+                    };
+                }
+            }
+        }
+
+        match successor(program, line, command) {
+            Some(next) => line = next,
+            None => break,
+        }
+    }
+
+    Err(SimpleError::new("no single flip repairs the program"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_program;
+
+    #[test]
+    fn test_repair() {
+        assert_eq!(
+            repair(&example_program()),
+            Ok(Repair {
+                line: 7,
+                original: Command::Jmp(-4),
+                replacement: Command::Nop(-4),
+                acc: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_repair_not_needed() {
+        assert_eq!(
+            repair(&[Command::Acc(1), Command::Nop(0)]),
+            Err(SimpleError::new("program terminates without repair"))
+        );
+    }
+
+    #[test]
+    fn test_repair_impossible() {
+        assert_eq!(
+            repair(&[Command::Acc(1), Command::Jmp(-1), Command::Jmp(-2)]),
+            Err(SimpleError::new("no single flip repairs the program"))
+        );
+    }
+}
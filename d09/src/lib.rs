@@ -0,0 +1,315 @@
+use simple_error::SimpleError;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, Lines};
+use std::ops::Range;
+
+// The last `capacity` numbers together with how many pairs of them add up
+// to each sum. Only pairs of two different values count.
+//
+// Pushing a number touches every other number in the window once, so
+// checking a number is O(window) instead of O(window²).
+#[derive(Debug, Clone)]
+pub struct PairSums {
+    capacity: usize,
+    window: VecDeque<u64>,
+    sums: HashMap<u64, usize>,
+}
+
+impl PairSums {
+    pub fn new(capacity: usize) -> Self {
+        PairSums {
+            capacity,
+            window: VecDeque::with_capacity(capacity),
+            sums: HashMap::new(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.window.len() >= self.capacity
+    }
+
+    pub fn contains_sum(&self, value: u64) -> bool {
+        self.sums.contains_key(&value)
+    }
+
+    // add a number, dropping the oldest one when the window is full
+    pub fn push(&mut self, value: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.is_full() {
+            let oldest = self.window.pop_front().unwrap();
+            for other in self.window.iter().filter(|&&other| other != oldest) {
+                // sums which overflow were never added
+                if let Some(sum) = oldest.checked_add(*other) {
+                    let count = self.sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.sums.remove(&sum);
+                    }
+                }
+            }
+        }
+
+        for other in self.window.iter().filter(|&&other| other != value) {
+            if let Some(sum) = value.checked_add(*other) {
+                *self.sums.entry(sum).or_insert(0) += 1;
+            }
+        }
+        self.window.push_back(value);
+    }
+}
+
+// Checks numbers one at a time, only keeping the window in memory.
+#[derive(Debug, Clone)]
+pub struct Validator {
+    sums: PairSums,
+}
+
+impl Validator {
+    pub fn new(preamble: usize) -> Self {
+        Validator {
+            sums: PairSums::new(preamble),
+        }
+    }
+
+    // Whether the number is valid for the window before it. Numbers in the
+    // preamble are always valid. Invalid numbers still become part of the
+    // window, so the numbers after them are checked like in the full list.
+    pub fn check(&mut self, value: u64) -> bool {
+        let valid = !self.sums.is_full() || self.sums.contains_sum(value);
+        self.sums.push(value);
+        valid
+    }
+}
+
+// Iterator over every invalid number (with its index) read from a stream
+// with one number per line.
+pub struct InvalidNumbers<R> {
+    lines: Lines<R>,
+    validator: Validator,
+    index: usize,
+    line: usize,
+}
+
+impl<R: BufRead> Iterator for InvalidNumbers<R> {
+    type Item = Result<(usize, u64), SimpleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in &mut self.lines {
+            self.line += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(SimpleError::from(err))),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let value: u64 = match line.trim().parse() {
+                Ok(value) => value,
+                Err(_) => {
+                    return Some(Err(SimpleError::new(format!(
+                        "line {}: invalid number: {}",
+                        self.line, line
+                    ))))
+                }
+            };
+
+            let index = self.index;
+            self.index += 1;
+            if !self.validator.check(value) {
+                return Some(Ok((index, value)));
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cipher {
+    preamble: usize,
+}
+
+impl Default for Cipher {
+    fn default() -> Self {
+        Cipher::new(25)
+    }
+}
+
+impl Cipher {
+    pub fn new(preamble: usize) -> Self {
+        Cipher { preamble }
+    }
+
+    pub fn preamble(&self) -> usize {
+        self.preamble
+    }
+
+    // the first number after the preamble which is not the sum of two
+    // different numbers out of the `preamble` numbers before it
+    pub fn first_invalid(&self, numbers: &[u64]) -> Result<(usize, u64), SimpleError> {
+        let mut validator = self.validator();
+
+        numbers
+            .iter()
+            .enumerate()
+            .find(|(_, value)| !validator.check(**value))
+            .map(|(i, value)| (i, *value))
+            .ok_or_else(|| SimpleError::new("no wrong number found"))
+    }
+
+    pub fn validator(&self) -> Validator {
+        Validator::new(self.preamble)
+    }
+
+    // all invalid numbers in the stream, without reading it all at once
+    pub fn invalid_numbers<R: BufRead>(&self, reader: R) -> InvalidNumbers<R> {
+        InvalidNumbers {
+            lines: reader.lines(),
+            validator: self.validator(),
+            index: 0,
+            line: 0,
+        }
+    }
+
+    // The contiguous range of at least two numbers adding up to `target`.
+    //
+    // Since all numbers are positive the sum only grows when the end moves
+    // and only shrinks when the start moves, so two pointers are enough.
+    // The sum is an u128, a few big u64 numbers already add up to more than
+    // an u64 holds.
+    pub fn weakness_range(
+        &self,
+        numbers: &[u64],
+        target: u64,
+    ) -> Result<Range<usize>, SimpleError> {
+        let target = target as u128;
+        let mut start = 0;
+        let mut sum: u128 = 0;
+
+        for end in 0..numbers.len() {
+            sum += numbers[end] as u128;
+
+            while sum > target && start < end {
+                sum -= numbers[start] as u128;
+                start += 1;
+            }
+
+            if sum == target && end > start {
+                return Ok(start..end + 1);
+            }
+        }
+
+        Err(SimpleError::new("no combination found"))
+    }
+
+    // smallest plus largest number in the weakness range
+    pub fn weakness(&self, numbers: &[u64], target: u64) -> Result<u64, SimpleError> {
+        let range = &numbers[self.weakness_range(numbers, target)?];
+        range
+            .iter()
+            .min()
+            .unwrap()
+            .checked_add(*range.iter().max().unwrap())
+            .ok_or_else(|| SimpleError::new("weakness too large"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_data() -> Vec<u64> {
+        vec![
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
+            576,
+        ]
+    }
+
+    #[test]
+    fn pair_sums() {
+        let mut sums = PairSums::new(3);
+        sums.push(1);
+        sums.push(2);
+        sums.push(2);
+        assert!(sums.is_full());
+        assert!(sums.contains_sum(3));
+        // a number can't pair with itself or a copy of itself
+        assert!(!sums.contains_sum(4));
+
+        sums.push(5);
+        assert!(!sums.contains_sum(3));
+        assert!(sums.contains_sum(7));
+        sums.push(6);
+        assert!(!sums.contains_sum(3));
+        assert!(sums.contains_sum(11));
+    }
+
+    #[test]
+    fn first_invalid() {
+        assert_eq!(Cipher::new(5).first_invalid(&test_data()), Ok((14, 127)));
+    }
+
+    #[test]
+    fn first_invalid_distinct_pairs() {
+        // 10 is only 5 + 5
+        assert_eq!(Cipher::new(2).first_invalid(&[5, 5, 10]), Ok((2, 10)));
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        let input = "35\n20\n15\n25\n47\n40\n62\n55\n65\n95\n102\n117\n150\n\
+                     182\n127\n219\n299\n277\n309\n576\n1000\n";
+
+        assert_eq!(
+            Cipher::new(5)
+                .invalid_numbers(input.as_bytes())
+                .collect::<Result<Vec<(usize, u64)>, SimpleError>>(),
+            Ok(vec![(14, 127), (20, 1000)])
+        );
+    }
+
+    #[test]
+    fn invalid_numbers_error() {
+        let mut invalid = Cipher::new(1).invalid_numbers("1\n\n2\nx\n2\n".as_bytes());
+
+        assert_eq!(invalid.next(), Some(Ok((1, 2))));
+        assert_eq!(
+            invalid.next(),
+            Some(Err(SimpleError::new("line 4: invalid number: x")))
+        );
+        assert_eq!(invalid.next(), Some(Ok((2, 2))));
+        assert_eq!(invalid.next(), None);
+    }
+
+    #[test]
+    fn weakness() {
+        let cipher = Cipher::new(5);
+        assert_eq!(cipher.weakness_range(&test_data(), 127), Ok(2..6));
+        assert_eq!(cipher.weakness(&test_data(), 127), Ok(62));
+        assert_eq!(
+            cipher.weakness(&test_data(), 36),
+            Err(SimpleError::new("no combination found"))
+        );
+    }
+
+    #[test]
+    fn weakness_large_numbers() {
+        let cipher = Cipher::new(1);
+        assert_eq!(
+            cipher.weakness(&[u64::MAX, 1], 5),
+            Err(SimpleError::new("no combination found"))
+        );
+        assert_eq!(
+            cipher.weakness_range(&[u64::MAX, 3, u64::MAX - 4, 1], u64::MAX),
+            Ok(1..4)
+        );
+        assert_eq!(
+            cipher.weakness(&[u64::MAX, u64::MAX - 1, 1], u64::MAX),
+            Ok(u64::MAX)
+        );
+    }
+}
@@ -1,11 +1,20 @@
-#[macro_use]
-extern crate itertools;
-
-use simple_error::SimpleError;
+use d09::Cipher;
+use std::env;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
 
 fn main() {
+    // `d09 stream` reports every invalid number read from stdin
+    if env::args().nth(1).as_deref() == Some("stream") {
+        for result in Cipher::default().invalid_numbers(io::stdin().lock()) {
+            match result {
+                Ok((index, value)) => println!("{}: {}", index, value),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        return;
+    }
+
     let file = File::open("input.txt").unwrap();
 
     let input: Vec<u64> = BufReader::new(file)
@@ -13,60 +22,9 @@ fn main() {
         .map(|line| line.unwrap().parse().unwrap())
         .collect();
 
-    if let Ok(invalid_number) = run(&input, 25) {
+    let cipher = Cipher::new(25);
+    if let Ok((_, invalid_number)) = cipher.first_invalid(&input) {
         println!("part 1: {:?}", invalid_number);
-        println!("part 2: {:?}", run2(&input, invalid_number));
-    }
-}
-
-fn run(input: &Vec<u64>, check_window: usize) -> Result<u64, SimpleError> {
-    for slice in input.windows(check_window + 1) {
-        let current_value = slice.last().unwrap();
-        let check_values = &slice[..check_window];
-
-        let has_combinations = iproduct!(check_values.iter(), check_values.iter())
-            .any(|(a, b)| *a + *b == *current_value);
-
-        if !has_combinations {
-            return Ok(*current_value);
-        }
-    }
-
-    Err(SimpleError::new("no wrong number found"))
-}
-
-fn run2(input: &Vec<u64>, to_find: u64) -> Result<u64, SimpleError> {
-    for window_size in 2..=input.len() {
-        for slice in input.windows(window_size) {
-            if slice.iter().sum::<u64>() == to_find {
-                return Ok(slice.iter().min().unwrap() + slice.iter().max().unwrap());
-            }
-        }
-    }
-
-    Err(SimpleError::new("no combination found"))
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn test_data() -> Vec<u64> {
-        vec![
-            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
-            576,
-        ]
-    }
-
-    #[test]
-    fn it_works() {
-        let td = test_data();
-        assert_eq!(run(&td, 5), Ok(127));
-    }
-
-    #[test]
-    fn it_works2() {
-        let td = test_data();
-        assert_eq!(run2(&td, 127), Ok(62));
+        println!("part 2: {:?}", cipher.weakness(&input, invalid_number));
     }
 }
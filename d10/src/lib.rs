@@ -0,0 +1,371 @@
+use counter::Counter;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use simple_error::SimpleError;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainConfig {
+    // joltage of the charging outlet
+    pub outlet: u64,
+    // the device is rated this much higher than the highest adapter
+    pub device_offset: u64,
+    // differences an adapter can take between its input and its output
+    pub allowed_difference: RangeInclusive<u64>,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        ChainConfig {
+            outlet: 0,
+            device_offset: 3,
+            allowed_difference: 1..=3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrangementAnalysis {
+    pub count: BigUint,
+    // adapters used by every arrangement
+    pub mandatory: Vec<u64>,
+    // adapters used by some arrangements
+    pub optional: Vec<u64>,
+    // adapters no arrangement can use
+    pub unusable: Vec<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chain {
+    config: ChainConfig,
+    // outlet, sorted adapters, device
+    joltages: Vec<u64>,
+}
+
+impl Chain {
+    pub fn new(adapters: &[u64], config: ChainConfig) -> Self {
+        let mut sorted = adapters.to_vec();
+        sorted.sort_unstable();
+
+        let highest = sorted.last().copied().unwrap_or(config.outlet);
+        let mut joltages = Vec::with_capacity(sorted.len() + 2);
+        joltages.push(config.outlet);
+        joltages.extend(sorted);
+        // like every other joltage, the device is capped at u64::MAX
+        joltages.push(highest.saturating_add(config.device_offset));
+
+        Chain { config, joltages }
+    }
+
+    pub fn config(&self) -> &ChainConfig {
+        &self.config
+    }
+
+    pub fn device(&self) -> u64 {
+        *self.joltages.last().unwrap()
+    }
+
+    pub fn adapters(&self) -> &[u64] {
+        &self.joltages[1..self.joltages.len() - 1]
+    }
+
+    fn allowed(&self, from: usize, to: usize) -> bool {
+        self.joltages[to]
+            .checked_sub(self.joltages[from])
+            .is_some_and(|difference| self.config.allowed_difference.contains(&difference))
+    }
+
+    // how often every difference appears when all adapters are used
+    pub fn differences(&self) -> Result<Counter<u64>, SimpleError> {
+        let mut counter: Counter<u64> = Counter::new();
+
+        for i in 1..self.joltages.len() {
+            if !self.allowed(i - 1, i) {
+                return Err(SimpleError::new(format!(
+                    "can't connect {} jolts to {} jolts",
+                    self.joltages[i - 1],
+                    self.joltages[i]
+                )));
+            }
+            counter[&(self.joltages[i] - self.joltages[i - 1])] += 1;
+        }
+
+        Ok(counter)
+    }
+
+    // indices of the joltages which can follow the one at `index`
+    fn successors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let max = self.joltages[index].saturating_add(*self.config.allowed_difference.end());
+        ((index + 1)..self.joltages.len())
+            .take_while(move |&next| self.joltages[next] <= max)
+            .filter(move |&next| self.allowed(index, next))
+    }
+
+    // ways[i] is the number of ways from joltage i to the device.
+    // Built backwards over the sorted joltages, so no recursion needed.
+    fn ways_to_device(&self) -> Vec<BigUint> {
+        let last = self.joltages.len() - 1;
+        let mut ways: Vec<BigUint> = vec![BigUint::zero(); self.joltages.len()];
+        ways[last] = BigUint::one();
+
+        for index in (0..last).rev() {
+            let mut sum = BigUint::zero();
+            for next in self.successors(index) {
+                sum += &ways[next];
+            }
+            ways[index] = sum;
+        }
+
+        ways
+    }
+
+    // ways[i] is the number of ways from the outlet to joltage i
+    fn ways_from_outlet(&self) -> Vec<BigUint> {
+        let mut ways: Vec<BigUint> = vec![BigUint::zero(); self.joltages.len()];
+        ways[0] = BigUint::one();
+
+        for index in 0..self.joltages.len() - 1 {
+            if ways[index].is_zero() {
+                continue;
+            }
+            let current = ways[index].clone();
+            for next in self.successors(index) {
+                ways[next] += &current;
+            }
+        }
+
+        ways
+    }
+
+    // number of distinct adapter arrangements connecting outlet and device
+    pub fn arrangement_count(&self) -> Result<BigUint, SimpleError> {
+        let count = self.ways_to_device().swap_remove(0);
+        if count.is_zero() {
+            Err(SimpleError::new(format!(
+                "no chain from {} jolts to {} jolts",
+                self.config.outlet,
+                self.device()
+            )))
+        } else {
+            Ok(count)
+        }
+    }
+
+    // An adapter is in `arrangements through it` = `ways to reach it` *
+    // `ways from it to the device` arrangements. If that is all of them,
+    // the adapter is mandatory.
+    pub fn analyze_arrangements(&self) -> Result<ArrangementAnalysis, SimpleError> {
+        let count = self.arrangement_count()?;
+        let to_device = self.ways_to_device();
+        let from_outlet = self.ways_from_outlet();
+
+        let mut analysis = ArrangementAnalysis {
+            count,
+            mandatory: Vec::new(),
+            optional: Vec::new(),
+            unusable: Vec::new(),
+        };
+
+        for index in 1..self.joltages.len() - 1 {
+            let through = &from_outlet[index] * &to_device[index];
+            let joltage = self.joltages[index];
+
+            if through == analysis.count {
+                analysis.mandatory.push(joltage);
+            } else if through.is_zero() {
+                analysis.unusable.push(joltage);
+            } else {
+                analysis.optional.push(joltage);
+            }
+        }
+
+        Ok(analysis)
+    }
+
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        // which joltages have a way to the device at all, so the
+        // enumeration never walks into a dead end
+        let reaches_device: Vec<bool> = self
+            .ways_to_device()
+            .iter()
+            .map(|ways| !ways.is_zero())
+            .collect();
+
+        Arrangements {
+            stack: if reaches_device[0] {
+                vec![(0, 1)]
+            } else {
+                Vec::new()
+            },
+            chain: self,
+            reaches_device,
+        }
+    }
+}
+
+// Lazily enumerates every valid arrangement as the list of used adapters.
+pub struct Arrangements<'a> {
+    chain: &'a Chain,
+    reaches_device: Vec<bool>,
+    // used joltage indices and the next index to try after each of them
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let joltages = &self.chain.joltages;
+        let last = joltages.len() - 1;
+
+        while let Some(&(index, candidate)) = self.stack.last() {
+            if index == last {
+                let arrangement = self.stack[1..self.stack.len() - 1]
+                    .iter()
+                    .map(|(i, _)| joltages[*i])
+                    .collect();
+                self.stack.pop();
+                return Some(arrangement);
+            }
+
+            let too_far = candidate > last
+                || joltages[candidate]
+                    > joltages[index].saturating_add(*self.chain.config.allowed_difference.end());
+            if too_far {
+                self.stack.pop();
+                continue;
+            }
+
+            self.stack.last_mut().unwrap().1 += 1;
+            if self.reaches_device[candidate] && self.chain.allowed(index, candidate) {
+                self.stack.push((candidate, candidate + 1));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differences() {
+        let chain = Chain::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], Default::default());
+        let differences = chain.differences().unwrap();
+
+        assert_eq!(differences[&1], 7);
+        assert_eq!(differences[&3], 5);
+        assert_eq!(chain.device(), 22);
+    }
+
+    #[test]
+    fn differences_gap() {
+        let chain = Chain::new(&[1, 5], Default::default());
+        assert_eq!(
+            chain.differences(),
+            Err(SimpleError::new("can't connect 1 jolts to 5 jolts"))
+        );
+    }
+
+    #[test]
+    fn arrangements() {
+        let chain = Chain::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], Default::default());
+
+        let all: Vec<Vec<u64>> = chain.arrangements().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(all[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+        assert_eq!(chain.arrangement_count(), Ok(BigUint::from(8u32)));
+    }
+
+    #[test]
+    fn arrangements_large_joltages() {
+        let config = ChainConfig {
+            outlet: u64::MAX - 2,
+            ..Default::default()
+        };
+        let chain = Chain::new(&[u64::MAX - 1], config);
+
+        assert_eq!(chain.device(), u64::MAX);
+        assert_eq!(
+            chain.arrangements().collect::<Vec<Vec<u64>>>(),
+            vec![vec![u64::MAX - 1], vec![]]
+        );
+        assert_eq!(chain.arrangement_count(), Ok(BigUint::from(2u32)));
+    }
+
+    #[test]
+    fn arrangements_config() {
+        let config = ChainConfig {
+            outlet: 10,
+            device_offset: 1,
+            allowed_difference: 1..=2,
+        };
+        let chain = Chain::new(&[11, 12, 13], config);
+
+        assert_eq!(chain.device(), 14);
+        assert_eq!(
+            chain.arrangements().collect::<Vec<Vec<u64>>>(),
+            vec![
+                vec![11, 12, 13],
+                vec![11, 12],
+                vec![11, 13],
+                vec![12, 13],
+                vec![12],
+            ]
+        );
+        assert_eq!(chain.arrangement_count(), Ok(BigUint::from(5u32)));
+    }
+
+    #[test]
+    fn analyze_arrangements() {
+        let chain = Chain::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], Default::default());
+
+        assert_eq!(
+            chain.analyze_arrangements(),
+            Ok(ArrangementAnalysis {
+                count: BigUint::from(8u32),
+                mandatory: vec![1, 4, 7, 10, 12, 15, 16, 19],
+                optional: vec![5, 6, 11],
+                unusable: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn analyze_arrangements_unusable() {
+        let config = ChainConfig {
+            outlet: 0,
+            device_offset: 1,
+            allowed_difference: 1..=1,
+        };
+        // 2 and 2 can't follow each other, 3 needs one of them
+        let chain = Chain::new(&[1, 2, 2, 3], config);
+
+        let analysis = chain.analyze_arrangements().unwrap();
+        assert_eq!(analysis.count, BigUint::from(2u32));
+        assert_eq!(analysis.mandatory, vec![1, 3]);
+        assert_eq!(analysis.optional, vec![2, 2]);
+    }
+
+    #[test]
+    fn no_chain() {
+        let chain = Chain::new(&[1, 5], Default::default());
+        assert_eq!(
+            chain.arrangement_count(),
+            Err(SimpleError::new("no chain from 0 jolts to 8 jolts"))
+        );
+        assert_eq!(chain.arrangements().next(), None);
+    }
+
+    #[test]
+    fn arrangement_count_overflow() {
+        // 200 adapters one jolt apart have more arrangements than fit in u64
+        let adapters: Vec<u64> = (1..=200).collect();
+        let chain = Chain::new(&adapters, Default::default());
+
+        assert!(chain.arrangement_count().unwrap() > BigUint::from(u64::MAX));
+    }
+}
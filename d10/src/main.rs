@@ -1,17 +1,13 @@
-#[macro_use]
-extern crate lazy_static;
-
-use counter::Counter;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use d10::Chain;
+use num_bigint::BigUint;
+use simple_error::SimpleError;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::iter::FromIterator;
 
 fn main() {
     let file = File::open("input.txt").unwrap();
 
-    let input: Vec<u16> = BufReader::new(file)
+    let input: Vec<u64> = BufReader::new(file)
         .lines()
         .map(|line| line.unwrap().parse().unwrap())
         .collect();
@@ -20,62 +16,24 @@ fn main() {
     println!("part 2: {:?}", run2(&input));
 }
 
-fn run(input: &Vec<u16>) -> usize {
-    let mut sorted = input.clone();
-    sorted.sort();
-    sorted.insert(0, 0); // start at 0
-    sorted.push(sorted.last().unwrap() + 3); // builtin adapter
-
-    let counter = sorted
-        .windows(2)
-        .map(|slice| slice[1] - slice[0])
-        .collect::<Counter<_>>();
-
-    counter.get(&1).unwrap_or(&0) * counter.get(&3).unwrap_or(&0)
-}
-
-fn count_combinations(
-    values: &HashSet<u16>,
-    cache: &mut HashMap<u16, usize>,
-    start_at: u16,
-    end_at: u16,
-) -> usize {
-    if cache.contains_key(&start_at) {
-        return cache.get(&start_at).unwrap().clone();
-    }
-
-    let mut count = 0;
-    for step in 1..=3 {
-        let test = start_at + step;
-        if test == end_at {
-            count = 1;
-            break;
-        } else if values.contains(&test) {
-            count += count_combinations(values, cache, test, end_at);
-        }
-    }
+fn run(input: &[u64]) -> Result<usize, SimpleError> {
+    let counter = Chain::new(input, Default::default()).differences()?;
 
-    cache.insert(start_at, count);
-    count
+    Ok(counter.get(&1).unwrap_or(&0) * counter.get(&3).unwrap_or(&0))
 }
 
-fn run2(input: &Vec<u16>) -> usize {
-    let max = input.iter().max().unwrap();
-    let end_at = max + 3;
-
-    let values = HashSet::from_iter(input.iter().cloned());
-    let mut cache: HashMap<u16, usize> = HashMap::new();
-
-    count_combinations(&values, &mut cache, 0, end_at)
+fn run2(input: &[u64]) -> Result<BigUint, SimpleError> {
+    Chain::new(input, Default::default()).arrangement_count()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lazy_static::lazy_static;
 
     lazy_static! {
-        static ref TEST_DATA_1: Vec<u16> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4,];
-        static ref TEST_DATA_2: Vec<u16> = vec![
+        static ref TEST_DATA_1: Vec<u64> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4,];
+        static ref TEST_DATA_2: Vec<u64> = vec![
             28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35,
             8, 17, 7, 9, 4, 2, 34, 10, 3,
         ];
@@ -83,21 +41,21 @@ mod tests {
 
     #[test]
     fn part_1_works() {
-        assert_eq!(run(&TEST_DATA_1), 35);
+        assert_eq!(run(&TEST_DATA_1), Ok(35));
     }
 
     #[test]
     fn part_1_works_2() {
-        assert_eq!(run(&TEST_DATA_2), 220);
+        assert_eq!(run(&TEST_DATA_2), Ok(220));
     }
 
     #[test]
     fn part_2_works() {
-        assert_eq!(run2(&TEST_DATA_1), 8);
+        assert_eq!(run2(&TEST_DATA_1), Ok(BigUint::from(8u32)));
     }
 
     #[test]
     fn part_2_works_2() {
-        assert_eq!(run2(&TEST_DATA_2), 19208);
+        assert_eq!(run2(&TEST_DATA_2), Ok(BigUint::from(19208u32)));
     }
 }
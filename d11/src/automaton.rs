@@ -0,0 +1,298 @@
+use crate::{Cell, History, Seats};
+use std::collections::BTreeSet;
+use std::mem;
+
+// Counts the occupied seats a seat cares about.
+pub trait Neighbours {
+    fn occupied(&self, seats: &Seats, row: usize, col: usize) -> u8;
+
+    // The seats whose count depends on the given seat, if known. Then only
+    // seats next to a change are checked again in the next generation.
+    fn dependents(&self, _row: usize, _col: usize) -> Option<&[(usize, usize)]> {
+        None
+    }
+}
+
+impl<F> Neighbours for F
+where
+    F: Fn(&Seats, usize, usize) -> u8,
+{
+    fn occupied(&self, seats: &Seats, row: usize, col: usize) -> u8 {
+        self(seats, row, col)
+    }
+}
+
+// Birth / survival rules like in the game of life: an empty seat becomes
+// occupied when its number of occupied neighbours is in `born`, an occupied
+// seat stays occupied when the number is in `survives`. Floor never changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    born: BTreeSet<u8>,
+    survives: BTreeSet<u8>,
+}
+
+impl Rules {
+    pub fn new(born: &[u8], survives: &[u8]) -> Self {
+        Rules {
+            born: born.iter().copied().collect(),
+            survives: survives.iter().copied().collect(),
+        }
+    }
+
+    // The rules from the puzzle: empty seats without occupied neighbours
+    // get occupied, occupied seats with `threshold` or more get empty.
+    pub fn seating(threshold: u8) -> Self {
+        Rules {
+            born: [0].iter().copied().collect(),
+            survives: (0..threshold).collect(),
+        }
+    }
+
+    pub fn next(&self, cell: Cell, occupied: u8) -> Cell {
+        let occupy = match cell {
+            Cell::Floor => return Cell::Floor,
+            Cell::Empty => self.born.contains(&occupied),
+            Cell::Occupied => self.survives.contains(&occupied),
+        };
+        if occupy {
+            Cell::Occupied
+        } else {
+            Cell::Empty
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Outcome {
+    // nothing changes anymore from `generation` on
+    Stable { generation: usize },
+    // The seats repeat every `period` generations. `generation` is when the
+    // repetition was noticed, the cycle might have started before.
+    Cycle { generation: usize, period: usize },
+    // stopped after `generation` generations without converging
+    Limit { generation: usize },
+}
+
+// Runs the rules on the seats, one generation at a time. The next
+// generation is written into a second buffer which is then swapped with
+// the current one, so no seats are allocated while running.
+pub struct Automaton<N> {
+    rules: Rules,
+    neighbours: N,
+    current: Seats,
+    next: Seats,
+    generation: usize,
+    // seats to check in the next generation, `None` means all of them
+    check: Option<Vec<bool>>,
+    check_next: Vec<bool>,
+}
+
+impl<N: Neighbours> Automaton<N> {
+    pub fn new(seats: Seats, rules: Rules, neighbours: N) -> Self {
+        Automaton {
+            rules,
+            neighbours,
+            next: seats.clone(),
+            current: seats,
+            generation: 0,
+            check: None,
+            check_next: Vec::new(),
+        }
+    }
+
+    // Only check seats again when one of the seats they depend on changed
+    // in the last generation. Needs neighbours which know their dependents,
+    // without them every seat is still checked.
+    pub fn only_changed(mut self) -> Self {
+        let size = self.current.rows() * self.current.cols();
+        self.check = Some(vec![true; size]);
+        self.check_next = vec![false; size];
+        self
+    }
+
+    pub fn seats(&self) -> &Seats {
+        &self.current
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    // calculate the next generation, returns how many seats changed
+    pub fn step(&mut self) -> usize {
+        let cols = self.current.cols();
+        let mut changed = 0;
+
+        for row in 0..self.current.rows() {
+            for col in 0..cols {
+                let cell = self.current.data[row][col];
+                let skip = match &self.check {
+                    Some(check) => !check[row * cols + col],
+                    None => false,
+                };
+                let value = match cell {
+                    // floor, no need to count
+                    Cell::Floor => Cell::Floor,
+                    _ if skip => cell,
+                    _ => self
+                        .rules
+                        .next(cell, self.neighbours.occupied(&self.current, row, col)),
+                };
+                self.next.data[row][col] = value;
+
+                if value != cell {
+                    changed += 1;
+                    if self.check.is_some() {
+                        self.mark(row, col);
+                    }
+                }
+            }
+        }
+
+        mem::swap(&mut self.current, &mut self.next);
+        if let Some(check) = &mut self.check {
+            mem::swap(check, &mut self.check_next);
+            self.check_next.iter_mut().for_each(|c| *c = false);
+        }
+        self.generation += 1;
+        changed
+    }
+
+    // the seat changed, so it and everything depending on it need checking
+    fn mark(&mut self, row: usize, col: usize) {
+        let cols = self.current.cols();
+        match self.neighbours.dependents(row, col) {
+            Some(dependents) => {
+                self.check_next[row * cols + col] = true;
+                for (r, c) in dependents {
+                    self.check_next[r * cols + c] = true;
+                }
+            }
+            None => self.check_next.iter_mut().for_each(|c| *c = true),
+        }
+    }
+
+    // every generation from now on, see `History`
+    pub fn history(self, limit: usize) -> History<N> {
+        History::new(self, limit)
+    }
+
+    // Step until the seats are stable, repeat in a cycle or `limit`
+    // generations have run.
+    //
+    // Cycles are found with Brent's algorithm, which only needs to keep one
+    // earlier state around: it is replaced at every power of two, and the
+    // distance to it when it shows up again is the period.
+    pub fn run(&mut self, limit: usize) -> Outcome {
+        let mut saved = self.current.clone();
+        let mut power = 1;
+        let mut distance = 0;
+
+        while self.generation < limit {
+            if self.step() == 0 {
+                return Outcome::Stable {
+                    generation: self.generation - 1,
+                };
+            }
+
+            distance += 1;
+            if self.current == saved {
+                return Outcome::Cycle {
+                    generation: self.generation,
+                    period: distance,
+                };
+            }
+            if distance == power {
+                saved.clone_from(&self.current);
+                power *= 2;
+                distance = 0;
+            }
+        }
+
+        Outcome::Limit {
+            generation: self.generation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NeighbourTable;
+
+    fn adjacent(seats: &Seats, row: usize, col: usize) -> u8 {
+        let mut count = 0;
+        for r in row as i64 - 1..=row as i64 + 1 {
+            for c in col as i64 - 1..=col as i64 + 1 {
+                if (r, c) != (row as i64, col as i64) && seats.get(r, c) == Ok(Cell::Occupied) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    fn seats(lines: &[&str]) -> Seats {
+        Seats::from_lines(lines).unwrap()
+    }
+
+    #[test]
+    fn test_stable() {
+        let mut automaton = Automaton::new(
+            seats(&["L.LL", "LLLL", "L.L."]),
+            Rules::seating(4),
+            adjacent,
+        );
+
+        assert_eq!(automaton.step(), 9);
+        assert_eq!(automaton.seats().count(Cell::Occupied), 9);
+        assert_eq!(automaton.run(100), Outcome::Stable { generation: 2 });
+        assert_eq!(automaton.seats().to_string(), "#.L#\n#LLL\n#.#.\n");
+    }
+
+    #[test]
+    fn test_cycle() {
+        // every seat flips each generation
+        let mut automaton = Automaton::new(seats(&["L.L.L"]), Rules::new(&[0], &[]), adjacent);
+
+        assert_eq!(
+            automaton.run(100),
+            Outcome::Cycle {
+                generation: 3,
+                period: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_only_changed() {
+        let layout = seats(&[
+            "L.LL.LL.LL",
+            "LLLLLLL.LL",
+            "L.L.L..L..",
+            "LLLL.LL.LL",
+            "L.LL.LL.LL",
+            "L.LLLLL.LL",
+            "..L.L.....",
+            "LLLLLLLLLL",
+            "L.LLLLLL.L",
+            "L.LLLLL.LL",
+        ]);
+        let table = NeighbourTable::visible(&layout);
+        let mut all = Automaton::new(layout.clone(), Rules::seating(5), table.clone());
+        let mut changed = Automaton::new(layout, Rules::seating(5), table).only_changed();
+
+        while all.generation() < 10 {
+            assert_eq!(all.step(), changed.step());
+            assert_eq!(all.seats(), changed.seats());
+        }
+        assert_eq!(changed.seats().count(Cell::Occupied), 26);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut automaton = Automaton::new(seats(&["L.L.L"]), Rules::new(&[0], &[]), adjacent);
+        assert_eq!(automaton.run(1), Outcome::Limit { generation: 1 });
+        assert_eq!(automaton.seats().to_string(), "#.#.#\n");
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{Automaton, Cell, Neighbours, Seats};
+use gif::{Encoder, Frame, Repeat};
+use simple_error::SimpleError;
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use std::iter;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generation {
+    pub index: usize,
+    // seats which changed compared to the generation before
+    pub changed: usize,
+    pub seats: Seats,
+}
+
+// Every generation of an automaton, starting with the initial seats and
+// ending with the first stable one (or at the generation limit).
+pub struct History<N> {
+    automaton: Automaton<N>,
+    limit: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<N: Neighbours> History<N> {
+    pub fn new(automaton: Automaton<N>, limit: usize) -> Self {
+        History {
+            automaton,
+            limit,
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl<N: Neighbours> Iterator for History<N> {
+    type Item = Generation;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let changed = if !self.started {
+            self.started = true;
+            0
+        } else {
+            if self.done || self.automaton.generation() >= self.limit {
+                return None;
+            }
+            let changed = self.automaton.step();
+            if changed == 0 {
+                self.done = true;
+                return None;
+            }
+            changed
+        };
+
+        Some(Generation {
+            index: self.automaton.generation(),
+            changed,
+            seats: self.automaton.seats().clone(),
+        })
+    }
+}
+
+fn header(generation: &Generation) -> String {
+    format!(
+        "generation {}: {} changed, {} occupied",
+        generation.index,
+        generation.changed,
+        generation.seats.count(Cell::Occupied)
+    )
+}
+
+// all generations as text, each one after a header line
+pub fn write_log(
+    generations: impl IntoIterator<Item = Generation>,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    for generation in generations {
+        writeln!(output, "{}", header(&generation))?;
+        writeln!(output, "{}", generation.seats)?;
+    }
+    Ok(())
+}
+
+// Replays the generations in a terminal, redrawing the screen with ANSI
+// escape codes after every `delay`.
+pub fn animate(
+    generations: impl IntoIterator<Item = Generation>,
+    output: &mut impl Write,
+    delay: Duration,
+) -> io::Result<()> {
+    for generation in generations {
+        // clear the screen and move the cursor to the top left
+        write!(output, "\x1b[2J\x1b[H")?;
+        writeln!(output, "{}", header(&generation))?;
+        write!(output, "{}", generation.seats)?;
+        output.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+// floor, empty seat, occupied seat
+const PALETTE: [u8; 9] = [0x20, 0x20, 0x20, 0x70, 0x70, 0x70, 0xff, 0xa0, 0x00];
+
+// Writes the generations as an animated GIF which loops forever, every
+// seat drawn as a `scale` x `scale` square and every frame shown for
+// `delay` hundredths of a second.
+pub fn write_gif(
+    generations: impl IntoIterator<Item = Generation>,
+    output: impl Write,
+    scale: usize,
+    delay: u16,
+) -> Result<(), SimpleError> {
+    let mut generations = generations.into_iter().peekable();
+    let (rows, cols) = match generations.peek() {
+        Some(first) => (first.seats.rows(), first.seats.cols()),
+        None => return Err(SimpleError::new("no generations to draw")),
+    };
+
+    let size = |cells: usize| {
+        cells
+            .checked_mul(scale)
+            .and_then(|pixels| u16::try_from(pixels).ok())
+            .filter(|pixels| *pixels > 0)
+            .ok_or_else(|| SimpleError::new(format!("can't draw {} cells as a GIF", cells)))
+    };
+    let (width, height) = (size(cols)?, size(rows)?);
+
+    let mut encoder = Encoder::new(output, width, height, &PALETTE).map_err(SimpleError::from)?;
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(SimpleError::from)?;
+
+    for generation in generations {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for row in generation.seats.data.iter() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|cell| {
+                    let color = match cell {
+                        Cell::Floor => 0,
+                        Cell::Empty => 1,
+                        Cell::Occupied => 2,
+                    };
+                    iter::repeat_n(color, scale)
+                })
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&line);
+            }
+        }
+
+        let mut frame = Frame::from_indexed_pixels(width, height, &pixels, None);
+        frame.delay = delay;
+        encoder.write_frame(&frame).map_err(SimpleError::from)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NeighbourTable, Rules};
+
+    fn history() -> History<NeighbourTable> {
+        let seats = Seats::from_lines(["L.LL", "LLLL", "L.L."]).unwrap();
+        let table = NeighbourTable::adjacent(&seats);
+        Automaton::new(seats, Rules::seating(4), table).history(100)
+    }
+
+    #[test]
+    fn test_history() {
+        let generations: Vec<Generation> = history().collect();
+
+        assert_eq!(
+            generations
+                .iter()
+                .map(|g| (g.index, g.changed))
+                .collect::<Vec<(usize, usize)>>(),
+            vec![(0, 0), (1, 9), (2, 4)]
+        );
+        assert_eq!(generations[2].seats.to_string(), "#.L#\n#LLL\n#.#.\n");
+    }
+
+    #[test]
+    fn test_write_log() {
+        let mut output: Vec<u8> = Vec::new();
+        write_log(history().take(2), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "generation 0: 0 changed, 0 occupied\nL.LL\nLLLL\nL.L.\n\n\
+             generation 1: 9 changed, 9 occupied\n#.##\n####\n#.#.\n\n"
+        );
+    }
+
+    #[test]
+    fn test_write_gif() {
+        let mut output: Vec<u8> = Vec::new();
+        write_gif(history(), &mut output, 2, 10).unwrap();
+
+        assert!(output.starts_with(b"GIF89a"));
+        // logical screen size, little endian
+        assert_eq!(&output[6..10], &[8, 0, 6, 0]);
+        assert_eq!(output.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_write_gif_empty() {
+        assert_eq!(
+            write_gif(history().take(0), Vec::new(), 2, 10),
+            Err(SimpleError::new("no generations to draw"))
+        );
+        assert_eq!(
+            write_gif(history(), Vec::new(), 0, 10),
+            Err(SimpleError::new("can't draw 4 cells as a GIF"))
+        );
+    }
+}
@@ -1,10 +1,54 @@
 use simple_error::SimpleError;
+use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+mod automaton;
+mod history;
+mod neighbours;
+
+pub use automaton::{Automaton, Neighbours, Outcome, Rules};
+pub use history::{animate, write_gif, write_log, Generation, History};
+pub use neighbours::NeighbourTable;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Floor,
+    Empty,
+    Occupied,
+}
+
+impl Cell {
+    pub fn is_seat(self) -> bool {
+        self != Cell::Floor
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Cell::Floor => '.',
+            Cell::Empty => 'L',
+            Cell::Occupied => '#',
+        }
+    }
+}
+
+impl TryFrom<char> for Cell {
+    type Error = SimpleError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => Ok(Cell::Floor),
+            'L' => Ok(Cell::Empty),
+            '#' => Ok(Cell::Occupied),
+            _ => Err(SimpleError::new(format!("unknown cell: {:?}", c))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Seats {
     // TODO: use 2d vector
-    data: Vec<Vec<Option<bool>>>,
+    data: Vec<Vec<Cell>>,
     cols: usize,
     rows: usize,
 }
@@ -15,13 +59,7 @@ impl fmt::Display for Seats {
             writeln!(
                 f,
                 "{}",
-                line.iter()
-                    .map(|s| match s {
-                        None => ".",
-                        Some(false) => "L",
-                        Some(true) => "#",
-                    })
-                    .collect::<String>()
+                line.iter().map(|cell| cell.symbol()).collect::<String>()
             )?
         }
         Ok(())
@@ -31,33 +69,36 @@ impl fmt::Display for Seats {
 impl Seats {
     pub fn new(rows: usize, cols: usize) -> Self {
         Seats {
-            data: vec![vec![None; cols]; rows],
+            data: vec![vec![Cell::Floor; cols]; rows],
             cols,
             rows,
         }
     }
-    pub fn get(&self, row: i64, col: i64) -> Result<Option<bool>, SimpleError> {
+
+    fn index(&self, row: i64, col: i64) -> Result<(usize, usize), SimpleError> {
         if row < 0 || col < 0 || row >= (self.rows as i64) || col >= (self.cols as i64) {
-            Err(SimpleError::new("invalid row/col"))
+            Err(SimpleError::new(format!(
+                "invalid row/col: {}/{}",
+                row, col
+            )))
         } else {
-            Ok(self.data[row as usize][col as usize])
+            Ok((row as usize, col as usize))
         }
     }
 
-    pub fn set(&mut self, row: i64, col: i64, value: Option<bool>) -> Result<(), SimpleError> {
-        if row >= 0 && col >= 0 || row < (self.rows as i64) || col < (self.cols as i64) {
-            self.data[row as usize][col as usize] = value;
-            Ok(())
-        } else {
-            Err(SimpleError::new("invalid row/col"))
-        }
+    pub fn get(&self, row: i64, col: i64) -> Result<Cell, SimpleError> {
+        let (row, col) = self.index(row, col)?;
+        Ok(self.data[row][col])
     }
 
-    pub fn count(&self, which: Option<bool>) -> usize {
-        self.data
-            .iter()
-            .map(|row| row.iter().filter(|v| **v == which).count())
-            .sum()
+    pub fn set(&mut self, row: i64, col: i64, value: Cell) -> Result<(), SimpleError> {
+        let (row, col) = self.index(row, col)?;
+        self.data[row][col] = value;
+        Ok(())
+    }
+
+    pub fn count(&self, which: Cell) -> usize {
+        self.cells().filter(|(_, cell)| *cell == which).count()
     }
 
     pub fn cols(&self) -> usize {
@@ -68,32 +109,78 @@ impl Seats {
         self.rows
     }
 
-    pub fn from(lines: impl Iterator<Item = String>) -> Self {
-        let data: Vec<Vec<Option<bool>>> = lines
-            .map(|l| {
-                l.chars()
-                    .map(|c| match c {
-                        'L' => Some(false),
-                        '#' => Some(true),
-                        _ => None,
+    // every cell with its (row, col), row by row
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), Cell)> + '_ {
+        self.data.iter().enumerate().flat_map(|(row, line)| {
+            line.iter()
+                .enumerate()
+                .map(move |(col, cell)| ((row, col), *cell))
+        })
+    }
+
+    // One row per line, all rows need to have the same width. Errors point
+    // at the line and column (both starting at 1) of the problem.
+    pub fn from_lines<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+    ) -> Result<Self, SimpleError> {
+        let mut data: Vec<Vec<Cell>> = Vec::new();
+
+        for (i, line) in lines.into_iter().enumerate() {
+            let row = line
+                .as_ref()
+                .chars()
+                .enumerate()
+                .map(|(col, c)| {
+                    Cell::try_from(c).map_err(|err| {
+                        SimpleError::new(format!("line {}, column {}: {}", i + 1, col + 1, err))
                     })
-                    .collect()
-            })
-            .collect();
+                })
+                .collect::<Result<Vec<Cell>, SimpleError>>()?;
 
-        if data.len() > 0 {
+            if let Some(first) = data.first() {
+                if row.len() != first.len() {
+                    return Err(SimpleError::new(format!(
+                        "line {}: expected {} cells, found {}",
+                        i + 1,
+                        first.len(),
+                        row.len()
+                    )));
+                }
+            }
+            data.push(row);
+        }
+
+        if !data.is_empty() {
             let cols = data[0].len();
             let rows = data.len();
-            Seats { data, cols, rows }
+            Ok(Seats { data, cols, rows })
         } else {
-            Seats::new(0, 0)
+            Ok(Seats::new(0, 0))
         }
     }
 }
 
+impl FromStr for Seats {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Seats::from_lines(s.lines())
+    }
+}
+
+impl<S: AsRef<str>> TryFrom<&[S]> for Seats {
+    type Error = SimpleError;
+
+    fn try_from(lines: &[S]) -> Result<Self, Self::Error> {
+        Seats::from_lines(lines)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
     #[test]
     fn get_set() {
         let mut seats = Seats::new(3, 2);
@@ -101,10 +188,64 @@ mod tests {
         assert_eq!(seats.rows(), 3);
         assert_eq!(seats.cols(), 2);
 
-        assert_eq!(seats.set(2, 1, Some(true)), Ok(()));
-        assert_eq!(seats.get(2, 1), Ok(Some(true)));
+        assert_eq!(seats.set(2, 1, Cell::Occupied), Ok(()));
+        assert_eq!(seats.get(2, 1), Ok(Cell::Occupied));
 
         assert!(seats.get(5, 5).is_err());
         assert!(seats.get(3, 2).is_err());
     }
+
+    #[test]
+    fn set_out_of_bounds() {
+        let mut seats = Seats::new(3, 2);
+
+        assert_eq!(
+            seats.set(3, 0, Cell::Empty),
+            Err(SimpleError::new("invalid row/col: 3/0"))
+        );
+        assert_eq!(
+            seats.set(0, -1, Cell::Empty),
+            Err(SimpleError::new("invalid row/col: 0/-1"))
+        );
+        assert_eq!(seats, Seats::new(3, 2));
+    }
+
+    #[test]
+    fn parse() {
+        let seats: Seats = "L.#\n##L\n".parse().unwrap();
+
+        assert_eq!((seats.rows(), seats.cols()), (2, 3));
+        assert_eq!(seats.count(Cell::Occupied), 3);
+        assert_eq!(seats.to_string(), "L.#\n##L\n");
+        assert_eq!(
+            seats
+                .cells()
+                .take(2)
+                .collect::<Vec<((usize, usize), Cell)>>(),
+            vec![((0, 0), Cell::Empty), ((0, 1), Cell::Floor)]
+        );
+        assert_eq!(Seats::try_from(&["L.#", "##L"][..]), Ok(seats));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "L.L\nL.x\n".parse::<Seats>(),
+            Err(SimpleError::new("line 2, column 3: unknown cell: 'x'"))
+        );
+        assert_eq!(
+            "L.L\nL.\n".parse::<Seats>(),
+            Err(SimpleError::new("line 2: expected 3 cells, found 2"))
+        );
+        assert_eq!("".parse::<Seats>(), Ok(Seats::new(0, 0)));
+    }
+
+    #[test]
+    fn hash() {
+        let states: HashSet<Seats> = ["L#", "L#", "#L"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(states.len(), 2);
+    }
 }
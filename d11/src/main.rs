@@ -1,119 +1,87 @@
+#[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
 
-#[macro_use]
-extern crate itertools;
-
+use std::env;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
+use std::time::Duration;
 
-use d11::Seats;
+use d11::{animate, write_gif, write_log, Automaton, Cell, NeighbourTable, Outcome, Rules, Seats};
+use simple_error::SimpleError;
 
 fn main() {
     let file = File::open("input.txt").unwrap();
 
-    let seats = Seats::from(BufReader::new(file).lines().map(|line| line.unwrap()));
-
-    println!("part 1: {:?}", run(&seats, 4, &count_other_occupied_seats));
-    println!(
-        "part 2: {:?}",
-        run(&seats, 5, &count_other_occupied_seats_2)
-    );
-}
-
-lazy_static! {
-    static ref DIRECTIONS: Vec<(i64, i64)> = iproduct!(-1..=1, -1..=1)
-        .filter(|(r, c)| !(*r == 0 && *c == 0))
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .map(|line| line.unwrap())
         .collect();
-}
-
-fn count_other_occupied_seats_2(seats: &Seats, row: usize, col: usize) -> u8 {
-    let mut count = 0;
-
-    for (rd, cd) in DIRECTIONS.iter() {
-        let mut r = row as i64;
-        let mut c = col as i64;
-        loop {
-            r += rd;
-            c += cd;
-
-            if let Ok(cell) = seats.get(r, c) {
-                if let Some(value) = cell {
-                    if value == true {
-                        count += 1;
-                    }
-                    break;
-                }
-            } else {
-                break;
+    let seats = Seats::from_lines(&lines).unwrap();
+
+    // `d11 log`, `d11 animate` and `d11 gif <file>` show every generation
+    // of the part 2 simulation
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        let table = NeighbourTable::visible(&seats);
+        let history = Automaton::new(seats, Rules::seating(5), table).history(GENERATION_LIMIT);
+
+        match args
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+            .as_slice()
+        {
+            ["log"] => write_log(history, &mut io::stdout()).unwrap(),
+            ["animate"] => animate(history, &mut io::stdout(), Duration::from_millis(100)).unwrap(),
+            ["gif", path] => {
+                write_gif(history, File::create(path).unwrap(), 4, 10).unwrap();
             }
+            _ => eprintln!("usage: d11 [log | animate | gif <file>]"),
         }
+        return;
     }
 
-    count
+    println!(
+        "part 1: {:?}",
+        run(&seats, 4, NeighbourTable::adjacent(&seats))
+    );
+    println!(
+        "part 2: {:?}",
+        run(&seats, 5, NeighbourTable::visible(&seats))
+    );
 }
 
-fn count_other_occupied_seats(seats: &Seats, row: usize, col: usize) -> u8 {
-    DIRECTIONS
-        .iter()
-        .map(|(rd, cd)| ((row as i64) + rd, (col as i64) + cd))
-        .filter_map(|(r, c)| seats.get(r, c).ok())
-        .filter(|v| *v == Some(true))
-        .count() as u8
-}
+// more than enough for the puzzle input to settle
+const GENERATION_LIMIT: usize = 1000;
 
 fn run(
     seats: &Seats,
     too_many_seats_visible: u8,
-    seat_check_function: &dyn Fn(&Seats, usize, usize) -> u8,
-) -> usize {
-    let mut old_seats = seats.clone();
-
-    let mut did_change = true;
-    while did_change {
-        did_change = false;
-
-        let mut new_seats = Seats::new(seats.rows(), seats.cols());
-        for (row, col) in iproduct!(0..old_seats.rows(), 0..old_seats.cols()) {
-            new_seats
-                .set(
-                    row as i64,
-                    col as i64,
-                    match old_seats.get(row as i64, col as i64).unwrap() {
-                        None => None, // floor, leave empty
-                        Some(false) => {
-                            // If a seat is empty (L) and there are no occupied
-                            // seats adjacent to it, the seat becomes occupied.
-                            if seat_check_function(&old_seats, row, col) == 0 {
-                                did_change = true;
-                                Some(true)
-                            } else {
-                                Some(false)
-                            }
-                        }
-                        Some(true) => {
-                            // If a seat is occupied (#) and X our or more seats
-                            // adjacent to it are also occupied, the seat becomes empty.
-                            if seat_check_function(&old_seats, row, col) >= too_many_seats_visible {
-                                did_change = true;
-                                Some(false)
-                            } else {
-                                Some(true)
-                            }
-                        }
-                    },
-                )
-                .unwrap(); // error never happens since I iterate over the known cells
-        }
-        old_seats = new_seats;
+    neighbours: NeighbourTable,
+) -> Result<usize, SimpleError> {
+    let mut automaton = Automaton::new(
+        seats.clone(),
+        Rules::seating(too_many_seats_visible),
+        neighbours,
+    )
+    .only_changed();
+
+    match automaton.run(GENERATION_LIMIT) {
+        Outcome::Stable { .. } => Ok(automaton.seats().count(Cell::Occupied)),
+        outcome => Err(SimpleError::new(format!(
+            "seats did not settle: {:?}",
+            outcome
+        ))),
     }
-
-    old_seats.count(Some(true))
 }
 
 #[cfg(test)]
+// the test data predates clippy's useless_vec lint
+#[allow(clippy::useless_vec)]
 mod tests {
     use super::*;
+    use d11::Neighbours;
 
     lazy_static! {
         static ref TEST_DATA: Vec<String> = vec![
@@ -159,31 +127,31 @@ mod tests {
 
     #[test]
     fn part_1_works() {
-        let seats = Seats::from(TEST_DATA.iter().cloned());
-        assert_eq!(run(&seats, 4, &count_other_occupied_seats), 37);
+        let seats = Seats::from_lines(TEST_DATA.iter()).unwrap();
+        assert_eq!(run(&seats, 4, NeighbourTable::adjacent(&seats)), Ok(37));
     }
 
     #[test]
     fn new_seat_check() {
-        let seats = Seats::from(TEST_DATA_SEAT_CHECK_1.iter().cloned());
-        assert_eq!(count_other_occupied_seats_2(&seats, 4, 3), 8);
+        let seats = Seats::from_lines(TEST_DATA_SEAT_CHECK_1.iter()).unwrap();
+        assert_eq!(NeighbourTable::visible(&seats).occupied(&seats, 4, 3), 8);
     }
 
     #[test]
     fn new_seat_check_2() {
-        let seats = Seats::from(TEST_DATA_SEAT_CHECK_2.iter().cloned());
-        assert_eq!(count_other_occupied_seats_2(&seats, 1, 1), 0);
+        let seats = Seats::from_lines(TEST_DATA_SEAT_CHECK_2.iter()).unwrap();
+        assert_eq!(NeighbourTable::visible(&seats).occupied(&seats, 1, 1), 0);
     }
 
     #[test]
     fn new_seat_check_3() {
-        let seats = Seats::from(TEST_DATA_SEAT_CHECK_3.iter().cloned());
-        assert_eq!(count_other_occupied_seats_2(&seats, 3, 3), 0);
+        let seats = Seats::from_lines(TEST_DATA_SEAT_CHECK_3.iter()).unwrap();
+        assert_eq!(NeighbourTable::visible(&seats).occupied(&seats, 3, 3), 0);
     }
 
     #[test]
     fn part_2_works() {
-        let seats = Seats::from(TEST_DATA.iter().cloned());
-        assert_eq!(run(&seats, 5, &count_other_occupied_seats_2), 26);
+        let seats = Seats::from_lines(TEST_DATA.iter()).unwrap();
+        assert_eq!(run(&seats, 5, NeighbourTable::visible(&seats)), Ok(26));
     }
 }
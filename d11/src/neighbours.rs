@@ -0,0 +1,139 @@
+use crate::{Cell, Neighbours, Seats};
+
+const DIRECTIONS: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// The seats every seat looks at, computed once per layout. Floor never
+// changes, so the table stays valid for every generation of the layout it
+// was built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeighbourTable {
+    cols: usize,
+    neighbours: Vec<Vec<(usize, usize)>>,
+}
+
+impl NeighbourTable {
+    fn build(seats: &Seats, find: impl Fn(i64, i64, i64, i64) -> Option<(usize, usize)>) -> Self {
+        let mut neighbours = Vec::with_capacity(seats.rows() * seats.cols());
+
+        for row in 0..seats.rows() {
+            for col in 0..seats.cols() {
+                let list = if seats.data[row][col].is_seat() {
+                    DIRECTIONS
+                        .iter()
+                        .filter_map(|(rd, cd)| find(row as i64, col as i64, *rd, *cd))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                neighbours.push(list);
+            }
+        }
+
+        NeighbourTable {
+            cols: seats.cols(),
+            neighbours,
+        }
+    }
+
+    // the seats right next to every seat (part 1)
+    pub fn adjacent(seats: &Seats) -> Self {
+        NeighbourTable::build(seats, |row, col, rd, cd| {
+            let (r, c) = (row + rd, col + cd);
+            match seats.get(r, c) {
+                Ok(cell) if cell.is_seat() => Some((r as usize, c as usize)),
+                _ => None,
+            }
+        })
+    }
+
+    // the first seat visible in each direction (part 2)
+    pub fn visible(seats: &Seats) -> Self {
+        NeighbourTable::build(seats, |row, col, rd, cd| {
+            let (mut r, mut c) = (row + rd, col + cd);
+            while let Ok(cell) = seats.get(r, c) {
+                if cell.is_seat() {
+                    return Some((r as usize, c as usize));
+                }
+                r += rd;
+                c += cd;
+            }
+            None
+        })
+    }
+
+    pub fn neighbours(&self, row: usize, col: usize) -> &[(usize, usize)] {
+        &self.neighbours[row * self.cols + col]
+    }
+}
+
+impl Neighbours for NeighbourTable {
+    fn occupied(&self, seats: &Seats, row: usize, col: usize) -> u8 {
+        self.neighbours(row, col)
+            .iter()
+            .filter(|(r, c)| seats.data[*r][*c] == Cell::Occupied)
+            .count() as u8
+    }
+
+    // both ways of looking are symmetric, the seats a seat looks at are
+    // the ones looking at it
+    fn dependents(&self, row: usize, col: usize) -> Option<&[(usize, usize)]> {
+        Some(self.neighbours(row, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seats(lines: &[&str]) -> Seats {
+        Seats::from_lines(lines).unwrap()
+    }
+
+    #[test]
+    fn test_visible() {
+        let seats = seats(&[
+            ".......#.",
+            "...#.....",
+            ".#.......",
+            ".........",
+            "..#L....#",
+            "....#....",
+            ".........",
+            "#........",
+            "...#.....",
+        ]);
+        let table = NeighbourTable::visible(&seats);
+
+        assert_eq!(table.occupied(&seats, 4, 3), 8);
+        assert_eq!(table.neighbours(4, 3)[0], (2, 1));
+        assert_eq!(table.neighbours(0, 0), &[]);
+    }
+
+    #[test]
+    fn test_visible_blocked() {
+        let seats = seats(&[".............", ".L.L.#.#.#.#.", "............."]);
+        let table = NeighbourTable::visible(&seats);
+
+        assert_eq!(table.neighbours(1, 1), &[(1, 3)]);
+        assert_eq!(table.occupied(&seats, 1, 1), 0);
+    }
+
+    #[test]
+    fn test_adjacent() {
+        let seats = seats(&["#.#", "L#.", "..#"]);
+        let table = NeighbourTable::adjacent(&seats);
+
+        assert_eq!(table.neighbours(1, 1), &[(0, 0), (0, 2), (1, 0), (2, 2)]);
+        assert_eq!(table.occupied(&seats, 1, 1), 3);
+        assert_eq!(table.occupied(&seats, 2, 2), 1);
+    }
+}
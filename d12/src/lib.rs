@@ -1,8 +1,17 @@
 use num::ToPrimitive;
 use num::{Integer, Signed};
 use simple_error::SimpleError;
+use std::fmt;
 use std::str::FromStr;
 
+mod navigation;
+mod planner;
+mod track;
+
+pub use navigation::{Navigator, Orientation, Ship, WaypointShip};
+pub use planner::{plan_ship, plan_waypoint, to_instructions};
+pub use track::{Track, TrackPoint, TrackStats};
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Position {
     pub east: i64,
@@ -19,80 +28,161 @@ impl Position {
     }
 
     pub fn change(&mut self, heading: Heading, value: i64) -> &mut Self {
-        match heading {
-            Heading::North => {
-                self.north += value;
-            }
-            Heading::South => {
-                self.north -= value;
-            }
-            Heading::East => {
-                self.east += value;
-            }
-            Heading::West => {
-                self.east -= value;
-            }
-        }
+        self.advance(Bearing::from(heading), value)
+    }
+
+    // move `value` along the bearing, see `Bearing::offset` for rounding
+    pub fn advance(&mut self, bearing: Bearing, value: i64) -> &mut Self {
+        let (east, north) = bearing.offset(value);
+        self.east += east;
+        self.north += north;
         self
     }
+
+    // Rotate around the origin, `degrees` clockwise. Exact for multiples of
+    // 90, otherwise both coordinates are rounded to the nearest integer
+    // (halves away from zero), see `rotate_fine`.
+    pub fn rotate(&self, degrees: i64) -> Position {
+        let degrees = degrees.rem_euclid(360);
+        let turned = match degrees / 90 {
+            0 => *self,
+            1 => Position::new(self.north, -self.east),
+            2 => Position::new(-self.east, -self.north),
+            _ => Position::new(-self.north, self.east),
+        };
+        turned.rotate_fine(degrees % 90)
+    }
+
+    // Rotate by less than 90 degrees. Only sine and cosine are floating
+    // point, they are turned into fixed point numbers so the coordinates are
+    // multiplied as integers without losing precision. Results beyond i64
+    // are capped.
+    fn rotate_fine(&self, degrees: i64) -> Position {
+        const BITS: u32 = 62;
+        if degrees == 0 {
+            return *self;
+        }
+
+        let fixed = |v: f64| (v * (1u64 << BITS) as f64).round() as i128;
+        let unscale = |v: i128| {
+            let half = 1i128 << (BITS - 1);
+            let rounded = if v < 0 {
+                -((-v + half) >> BITS)
+            } else {
+                (v + half) >> BITS
+            };
+            rounded.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+        };
+
+        let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+        let (sin, cos) = (fixed(sin), fixed(cos));
+        let (east, north) = (self.east as i128, self.north as i128);
+        Position::new(
+            unscale(east * cos + north * sin),
+            unscale(north * cos - east * sin),
+        )
+    }
+}
+
+// Any direction in whole degrees, clockwise from north. Always in 0..360.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bearing(i64);
+
+impl Bearing {
+    pub fn new(degrees: i64) -> Self {
+        Bearing(degrees.rem_euclid(360))
+    }
+
+    pub fn degrees(self) -> i64 {
+        self.0
+    }
+
+    pub fn turn(self, by: i64) -> Self {
+        Bearing::new(self.0 + by.rem_euclid(360))
+    }
+
+    // (east, north) when moving `distance` along the bearing: north turned
+    // by the bearing, rounded like `Position::rotate`. So moving 5 north east
+    // goes 4 east and 4 north.
+    pub fn offset(self, distance: i64) -> (i64, i64) {
+        let offset = Position::new(0, distance).rotate(self.0);
+        (offset.east, offset.north)
+    }
+}
+
+impl From<Heading> for Bearing {
+    fn from(heading: Heading) -> Self {
+        Bearing(u16::from(heading) as i64)
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Heading {
     North,
-    South,
+    NorthEast,
     East,
+    SouthEast,
+    South,
+    SouthWest,
     West,
+    NorthWest,
+}
+
+const COMPASS: [Heading; 8] = [
+    Heading::North,
+    Heading::NorthEast,
+    Heading::East,
+    Heading::SouthEast,
+    Heading::South,
+    Heading::SouthWest,
+    Heading::West,
+    Heading::NorthWest,
+];
+
+// The only way a bearing becomes a heading: the nearest compass point, like
+// `Position::rotate` rounds to the nearest integer. Bearings are whole
+// degrees and the points are 45 apart, so there are no ties: 22 is north,
+// 23 north east and 300 north west.
+impl From<Bearing> for Heading {
+    fn from(bearing: Bearing) -> Self {
+        COMPASS[((bearing.degrees() + 22) / 45 % 8) as usize]
+    }
 }
 
+// degrees clockwise from north, like `Bearing`
 impl<T> From<T> for Heading
 where
     T: Signed + Integer + ToPrimitive,
 {
     fn from(value: T) -> Self {
-        let value_ = ToPrimitive::to_i64(&value).unwrap();
-
-        let v = if value_ >= 0 {
-            value_ % 360
-        } else {
-            360 - (value_.abs() % 360)
-        };
-
-        match v {
-            0..=89 => Heading::North,
-            90..=179 => Heading::East,
-            180..=269 => Heading::South,
-            270..=359 => Heading::West,
-            360 => Heading::North,
-            _ => {
-                // This cannot happen because of the code before
-                panic!("unknown heading value");
-            }
-        }
+        Heading::from(Bearing::new(ToPrimitive::to_i64(&value).unwrap()))
     }
 }
 
 impl From<Heading> for u16 {
     fn from(value: Heading) -> Self {
-        match value {
-            Heading::North => 0,
-            Heading::East => 90,
-            Heading::South => 180,
-            Heading::West => 270,
-        }
+        COMPASS.iter().position(|h| *h == value).unwrap() as u16 * 45
     }
 }
 
 impl Heading {
     pub fn turn(&self, by: i64) -> Self {
-        let mut heading = u16::from(*self) as i64;
-        if by < 0 {
-            heading = (heading + (360 + by)).abs() % 360
-        } else {
-            heading = (heading + by).abs() % 360
-        }
+        Heading::from(Bearing::from(*self).turn(by))
+    }
+}
 
-        Heading::from(heading)
+impl fmt::Display for Heading {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Heading::North => "N",
+            Heading::NorthEast => "NE",
+            Heading::East => "E",
+            Heading::SouthEast => "SE",
+            Heading::South => "S",
+            Heading::SouthWest => "SW",
+            Heading::West => "W",
+            Heading::NorthWest => "NW",
+        })
     }
 }
 
@@ -109,6 +199,18 @@ pub enum Action {
     Forward(i64),
 }
 
+// the same format `FromStr` reads, like `N3`, `R90` or `F10`
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Move(heading, value) => write!(f, "{}{}", heading, value),
+            Action::Turn(TurnDirection::Left, value) => write!(f, "L{}", value),
+            Action::Turn(TurnDirection::Right, value) => write!(f, "R{}", value),
+            Action::Forward(value) => write!(f, "F{}", value),
+        }
+    }
+}
+
 impl FromStr for Action {
     type Err = SimpleError;
 
@@ -117,7 +219,11 @@ impl FromStr for Action {
             return Err(SimpleError::new("string too short"));
         }
 
-        let (cmd, val) = action_str.split_at(1);
+        // the command is one letter, or two for intercardinal moves (NE5)
+        let split = action_str
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(action_str.len());
+        let (cmd, val) = action_str.split_at(split);
 
         if let Ok(value) = val.parse::<i64>() {
             match cmd {
@@ -125,6 +231,10 @@ impl FromStr for Action {
                 "S" => Ok(Action::Move(Heading::South, value)),
                 "E" => Ok(Action::Move(Heading::East, value)),
                 "W" => Ok(Action::Move(Heading::West, value)),
+                "NE" => Ok(Action::Move(Heading::NorthEast, value)),
+                "SE" => Ok(Action::Move(Heading::SouthEast, value)),
+                "SW" => Ok(Action::Move(Heading::SouthWest, value)),
+                "NW" => Ok(Action::Move(Heading::NorthWest, value)),
                 "L" => Ok(Action::Turn(TurnDirection::Left, value)),
                 "R" => Ok(Action::Turn(TurnDirection::Right, value)),
                 "F" => Ok(Action::Forward(value)),
@@ -144,8 +254,19 @@ mod tests {
     #[test_case("F10", Action::Forward(10))]
     #[test_case("N3", Action::Move(Heading::North, 3))]
     #[test_case("R90", Action::Turn(TurnDirection::Right, 90))]
+    #[test_case("L45", Action::Turn(TurnDirection::Left, 45))]
+    #[test_case("NE5", Action::Move(Heading::NorthEast, 5))]
+    #[test_case("SW2", Action::Move(Heading::SouthWest, 2))]
     fn test_actions_from_str(s: &str, expected: Action) {
         assert_eq!(s.parse::<Action>(), Ok(expected));
+        assert_eq!(expected.to_string(), s);
+    }
+
+    #[test_case("N", "string too short")]
+    #[test_case("NN3", "invalid command")]
+    #[test_case("F1x", "could not parse number")]
+    fn test_actions_from_str_error(s: &str, expected: &str) {
+        assert_eq!(s.parse::<Action>(), Err(SimpleError::new(expected)));
     }
 
     #[test]
@@ -161,6 +282,49 @@ mod tests {
         );
     }
 
+    #[test_case(Position::new(10, 4), 90, Position::new(4, -10); "from north east")]
+    #[test_case(Position::new(4, -10), 90, Position::new(-10, -4); "from south east")]
+    #[test_case(Position::new(-10, -4), 90, Position::new(-4, 10); "from south west")]
+    #[test_case(Position::new(-4, 10), 90, Position::new(10, 4); "from north west")]
+    #[test_case(Position::new(10, 4), -90, Position::new(-4, 10); "left 90")]
+    #[test_case(Position::new(10, 4), 0, Position::new(10, 4); "no turn")]
+    #[test_case(Position::new(10, 4), 540, Position::new(-10, -4); "right 540")]
+    #[test_case(Position::new(10, 0), 45, Position::new(7, -7); "right 45")]
+    #[test_case(Position::new(10, 0), -30, Position::new(9, 5); "left 30")]
+    #[test_case(Position::new(10, 4), 360 + 90, Position::new(4, -10); "right 450")]
+    #[test_case(Position::new(3, 4), 120, Position::new(2, -5); "right 120")]
+    fn test_position_rotate(position: Position, degrees: i64, expected: Position) {
+        assert_eq!(position.rotate(degrees), expected);
+    }
+
+    #[test]
+    fn test_position_rotate_large() {
+        // beyond what f64 stores exactly, the last bit still counts
+        let position = Position::new((1 << 60) + 1, 0);
+        assert_eq!(position.rotate(90), Position::new(0, -(1 << 60) - 1));
+        assert_eq!(Position::new(i64::MAX, i64::MAX).rotate(45).east, i64::MAX);
+    }
+
+    #[test_case(0, 5, (0, 5))]
+    #[test_case(45, 5, (4, 4))]
+    #[test_case(225, 5, (-4, -4))]
+    #[test_case(270, 5, (-5, 0))]
+    #[test_case(30, 10, (5, 9))]
+    #[test_case(-90, 5, (-5, 0); "minus 90")]
+    fn test_bearing_offset(degrees: i64, distance: i64, expected: (i64, i64)) {
+        assert_eq!(Bearing::new(degrees).offset(distance), expected);
+    }
+
+    #[test]
+    fn test_position_change() {
+        let mut position = Position::new(0, 0);
+        position
+            .change(Heading::NorthEast, 5)
+            .change(Heading::West, 1)
+            .advance(Bearing::new(180), 2);
+        assert_eq!(position, Position::new(3, 2));
+    }
+
     #[test_case(-450, Heading::West ;"-450" )]
     #[test_case(-360, Heading::North ; "-360" )]
     #[test_case(-270, Heading::East; "-270" )]
@@ -170,8 +334,15 @@ mod tests {
     #[test_case(90, Heading::East)]
     #[test_case(180, Heading::South)]
     #[test_case(188, Heading::South)]
+    #[test_case(203, Heading::SouthWest)]
     #[test_case(270, Heading::West)]
-    #[test_case(300, Heading::West)]
+    #[test_case(300, Heading::NorthWest)]
+    #[test_case(22, Heading::North)]
+    #[test_case(23, Heading::NorthEast)]
+    #[test_case(45, Heading::NorthEast)]
+    #[test_case(89, Heading::East)]
+    #[test_case(337, Heading::NorthWest)]
+    #[test_case(338, Heading::North)]
     #[test_case(360, Heading::North)]
     #[test_case(450, Heading::East)]
     fn test_heading_from(input: i16, expected: Heading) {
@@ -189,6 +360,12 @@ mod tests {
     #[test_case(Heading::West, -270, Heading::North; "west/-270")]
     #[test_case(Heading::West, -360, Heading::West; "west/-360" )]
     #[test_case(Heading::West, -450, Heading::South; "west/-450")]
+    #[test_case(Heading::West, 45, Heading::NorthWest)]
+    #[test_case(Heading::NorthEast, 90, Heading::SouthEast)]
+    #[test_case(Heading::North, 44, Heading::NorthEast)]
+    #[test_case(Heading::North, 45, Heading::NorthEast)]
+    #[test_case(Heading::North, -1, Heading::North; "north/-1")]
+    #[test_case(Heading::North, -23, Heading::NorthWest; "north/-23")]
     fn test_heading_turn(initial: Heading, turn: i64, expected: Heading) {
         assert_eq!(initial.turn(turn), expected);
     }
@@ -1,5 +1,9 @@
-use d12::{Action, Heading, Position, TurnDirection};
+use d12::{
+    plan_ship, plan_waypoint, to_instructions, Action, Navigator, Position, Ship, Track,
+    WaypointShip,
+};
 use simple_error::SimpleError;
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
@@ -11,71 +15,60 @@ fn main() {
         .map(|line| line.unwrap().parse().unwrap())
         .collect();
 
-    println!("part 1: {:?}", run(&actions));
-    println!("part 2: {:?}", run_2(&actions));
-}
+    // `d12 plan <east> <north> [waypoint]` prints instructions to get there
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("plan") {
+        let coordinate = |i: usize| args.get(i).and_then(|v| v.parse().ok());
+        let target = match (coordinate(1), coordinate(2)) {
+            (Some(east), Some(north)) => Position::new(east, north),
+            _ => {
+                eprintln!("usage: d12 plan <east> <north> [waypoint]");
+                return;
+            }
+        };
+        let plan = match args.get(3).map(String::as_str) {
+            Some("waypoint") => plan_waypoint(&WaypointShip::default(), target, None),
+            _ => plan_ship(&Ship::default(), target, None),
+        };
+        print!("{}", to_instructions(&plan));
+        return;
+    }
 
-fn run(actions: &Vec<Action>) -> Result<i64, SimpleError> {
-    let mut position: Position = Position::new(0, 0);
-    let mut current_heading = Heading::East;
+    // `d12 <stats|geojson|svg> [waypoint]` prints the recorded voyage
+    if let Some(format) = env::args().nth(1) {
+        let track = match env::args().nth(2).as_deref() {
+            Some("waypoint") => Track::record(&mut WaypointShip::default(), &actions),
+            _ => Track::record(&mut Ship::default(), &actions),
+        }
+        .unwrap();
 
-    for action in actions {
-        match action {
-            Action::Move(direction, value) => {
-                position.change(*direction, *value);
-            }
-            Action::Turn(direction, value) => match direction {
-                TurnDirection::Left => {
-                    current_heading = current_heading.turn(value * -1);
-                }
-                TurnDirection::Right => {
-                    current_heading = current_heading.turn(*value);
-                }
-            },
-            Action::Forward(value) => {
-                position.change(current_heading, *value);
-            }
+        match format.as_str() {
+            "stats" => println!("{:?}", track.stats()),
+            "geojson" => println!("{}", track.to_geojson()),
+            "svg" => print!("{}", track.to_svg()),
+            _ => eprintln!("usage: d12 [stats|geojson|svg [waypoint]]"),
         }
+        return;
     }
 
-    Ok(position.manhattan_distance(&Position::new(0, 0)))
+    println!("part 1: {:?}", run(&actions));
+    println!("part 2: {:?}", run_2(&actions));
 }
 
-fn new_heading_for_waypoint(pos: &Position, turn: i64) -> Result<Position, SimpleError> {
-    match turn {
-        90 | -270 => Ok(Position::new(pos.north, pos.east * -1)),
-        180 | -180 => Ok(Position::new(pos.east * -1, pos.north * -1)),
-        270 | -90 => Ok(Position::new(pos.north * -1, pos.east)),
-        0 => Ok(Position::new(pos.north, pos.east)),
-        _ => Err(SimpleError::new("unknown turn")),
-    }
+fn distance_travelled(
+    navigator: &mut impl Navigator,
+    actions: &[Action],
+) -> Result<i64, SimpleError> {
+    let start = navigator.position();
+    Ok(navigator.navigate(actions)?.manhattan_distance(&start))
 }
 
-fn run_2(actions: &Vec<Action>) -> Result<i64, SimpleError> {
-    let mut waypoint = Position::new(10, 1);
-    let mut ship = Position::new(0, 0);
-
-    for action in actions {
-        match action {
-            Action::Move(direction, value) => {
-                waypoint.change(*direction, *value);
-            }
-            Action::Forward(value) => {
-                ship.east += value * waypoint.east;
-                ship.north += value * waypoint.north;
-            }
-            Action::Turn(direction, value) => match direction {
-                TurnDirection::Left => {
-                    waypoint = new_heading_for_waypoint(&waypoint, value * -1)?;
-                }
-                TurnDirection::Right => {
-                    waypoint = new_heading_for_waypoint(&waypoint, *value)?;
-                }
-            },
-        }
-    }
+fn run(actions: &[Action]) -> Result<i64, SimpleError> {
+    distance_travelled(&mut Ship::default(), actions)
+}
 
-    Ok(ship.manhattan_distance(&Position::new(0, 0)))
+fn run_2(actions: &[Action]) -> Result<i64, SimpleError> {
+    distance_travelled(&mut WaypointShip::default(), actions)
 }
 
 #[cfg(test)]
@@ -84,32 +77,12 @@ mod tests {
     use lazy_static::lazy_static;
 
     lazy_static! {
-        static ref TEST_DATA: Vec<Action> = vec!["F10", "N3", "F7", "R90", "F11",]
+        static ref TEST_DATA: Vec<Action> = ["F10", "N3", "F7", "R90", "F11",]
             .iter()
             .map(|s| s.parse().unwrap())
             .collect();
     }
 
-    #[test]
-    fn new_waypoint_heading() {
-        assert_eq!(
-            new_heading_for_waypoint(&Position::new(10, 4), 90),
-            Ok(Position::new(4, -10))
-        );
-        assert_eq!(
-            new_heading_for_waypoint(&Position::new(4, -10), 90),
-            Ok(Position::new(-10, -4))
-        );
-        assert_eq!(
-            new_heading_for_waypoint(&Position::new(-10, -4), 90),
-            Ok(Position::new(-4, 10))
-        );
-        assert_eq!(
-            new_heading_for_waypoint(&Position::new(-4, 10), 90),
-            Ok(Position::new(10, 4))
-        );
-    }
-
     #[test]
     fn part_1_works() {
         assert_eq!(run(&TEST_DATA), Ok(25));
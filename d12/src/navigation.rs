@@ -0,0 +1,236 @@
+use crate::{Action, Bearing, Heading, Position, TurnDirection};
+use simple_error::SimpleError;
+
+// A way of interpreting the navigation instructions. Implementations only
+// say what the three kinds of actions do, running a list of actions is
+// shared.
+pub trait Navigator {
+    // where the ship is
+    fn position(&self) -> Position;
+
+    // where the ship is going
+    fn orientation(&self) -> Orientation;
+
+    // N, S, E, W and the intercardinal points (NE, ...)
+    fn shift(&mut self, heading: Heading, value: i64) -> Result<(), SimpleError>;
+
+    // L and R, in degrees clockwise (left turns are negative)
+    fn turn(&mut self, degrees: i64) -> Result<(), SimpleError>;
+
+    // F
+    fn forward(&mut self, value: i64) -> Result<(), SimpleError>;
+
+    fn execute(&mut self, action: &Action) -> Result<(), SimpleError> {
+        match action {
+            Action::Move(heading, value) => self.shift(*heading, *value),
+            Action::Turn(TurnDirection::Left, value) => self.turn(-value),
+            Action::Turn(TurnDirection::Right, value) => self.turn(*value),
+            Action::Forward(value) => self.forward(*value),
+        }
+    }
+
+    // run all actions, returns where the ship ends up
+    fn navigate(&mut self, actions: &[Action]) -> Result<Position, SimpleError> {
+        self.navigate_with(actions, &mut |_, _| {})
+    }
+
+    // like `navigate`, `on_step` sees the navigator after every action
+    fn navigate_with(
+        &mut self,
+        actions: &[Action],
+        on_step: &mut dyn FnMut(&Self, &Action),
+    ) -> Result<Position, SimpleError> {
+        for (i, action) in actions.iter().enumerate() {
+            self.execute(action)
+                .map_err(|err| SimpleError::new(format!("action {}: {}", i + 1, err)))?;
+            on_step(self, action);
+        }
+        Ok(self.position())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Orientation {
+    Heading(Bearing),
+    // relative to the ship
+    Waypoint(Position),
+}
+
+// Part 1: the actions move the ship itself, F moves along its heading.
+// Turns can be any angle, not only the compass points.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ship {
+    pub position: Position,
+    pub heading: Bearing,
+}
+
+impl Ship {
+    pub fn new(position: Position, heading: Bearing) -> Self {
+        Ship { position, heading }
+    }
+}
+
+impl Default for Ship {
+    fn default() -> Self {
+        Ship::new(Position::new(0, 0), Bearing::from(Heading::East))
+    }
+}
+
+impl Navigator for Ship {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn orientation(&self) -> Orientation {
+        Orientation::Heading(self.heading)
+    }
+
+    fn shift(&mut self, heading: Heading, value: i64) -> Result<(), SimpleError> {
+        self.position.change(heading, value);
+        Ok(())
+    }
+
+    fn turn(&mut self, degrees: i64) -> Result<(), SimpleError> {
+        self.heading = self.heading.turn(degrees);
+        Ok(())
+    }
+
+    fn forward(&mut self, value: i64) -> Result<(), SimpleError> {
+        self.position.advance(self.heading, value);
+        Ok(())
+    }
+}
+
+// Part 2: N/S/E/W and turns move a waypoint relative to the ship, F moves
+// the ship towards the waypoint `value` times.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WaypointShip {
+    pub position: Position,
+    pub waypoint: Position,
+    // Turns in a row add up in whole degrees and the waypoint is rotated
+    // once from where it was before them, so rounding doesn't pile up.
+    // Only valid while the waypoint is still `to`.
+    rotation: Option<Rotation>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Rotation {
+    from: Position,
+    by: Bearing,
+    to: Position,
+}
+
+impl WaypointShip {
+    pub fn new(position: Position, waypoint: Position) -> Self {
+        WaypointShip {
+            position,
+            waypoint,
+            rotation: None,
+        }
+    }
+}
+
+impl Default for WaypointShip {
+    fn default() -> Self {
+        WaypointShip::new(Position::new(0, 0), Position::new(10, 1))
+    }
+}
+
+impl Navigator for WaypointShip {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn orientation(&self) -> Orientation {
+        Orientation::Waypoint(self.waypoint)
+    }
+
+    fn shift(&mut self, heading: Heading, value: i64) -> Result<(), SimpleError> {
+        self.waypoint.change(heading, value);
+        Ok(())
+    }
+
+    fn turn(&mut self, degrees: i64) -> Result<(), SimpleError> {
+        let (from, by) = match self.rotation {
+            Some(rotation) if rotation.to == self.waypoint => (rotation.from, rotation.by),
+            _ => (self.waypoint, Bearing::new(0)),
+        };
+        let by = by.turn(degrees);
+
+        self.waypoint = from.rotate(by.degrees());
+        self.rotation = Some(Rotation {
+            from,
+            by,
+            to: self.waypoint,
+        });
+        Ok(())
+    }
+
+    fn forward(&mut self, value: i64) -> Result<(), SimpleError> {
+        self.position.east += value * self.waypoint.east;
+        self.position.north += value * self.waypoint.north;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions() -> Vec<Action> {
+        ["F10", "N3", "F7", "R90", "F11"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ship() {
+        let mut ship = Ship::default();
+
+        assert_eq!(ship.navigate(&actions()), Ok(Position::new(17, -8)));
+        assert_eq!(ship.heading, Bearing::from(Heading::South));
+    }
+
+    #[test]
+    fn test_waypoint_ship() {
+        let mut ship = WaypointShip::default();
+
+        assert_eq!(ship.navigate(&actions()), Ok(Position::new(214, -72)));
+        assert_eq!(ship.waypoint, Position::new(4, -10));
+    }
+
+    #[test]
+    fn test_any_angle() {
+        let actions: Vec<Action> = ["R45", "F10", "L15", "F10"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let mut ship = Ship::new(Position::new(0, 0), Bearing::new(0));
+        assert_eq!(ship.navigate(&actions), Ok(Position::new(12, 16)));
+        assert_eq!(ship.heading, Bearing::new(30));
+
+        let mut ship = WaypointShip::new(Position::new(0, 0), Position::new(0, 10));
+        assert_eq!(ship.navigate(&actions), Ok(Position::new(120, 160)));
+        assert_eq!(ship.waypoint, Position::new(5, 9));
+    }
+
+    #[test]
+    fn test_turns_add_up_exactly() {
+        let mut ship = WaypointShip::new(Position::new(0, 0), Position::new(10, 3));
+        for _ in 0..90 {
+            ship.turn(1).unwrap();
+        }
+        assert_eq!(ship.waypoint, Position::new(3, -10));
+
+        ship.turn(-15).unwrap();
+        ship.turn(15).unwrap();
+        assert_eq!(ship.waypoint, Position::new(3, -10));
+
+        // moving the waypoint starts over from where it is
+        ship.shift(Heading::North, 1).unwrap();
+        ship.turn(90).unwrap();
+        assert_eq!(ship.waypoint, Position::new(-9, -3));
+    }
+}
@@ -0,0 +1,497 @@
+use crate::{
+    Action, Bearing, Heading, Navigator, Position, Ship, TurnDirection, WaypointShip, COMPASS,
+};
+use num::Integer;
+use std::convert::TryFrom;
+
+// Plans can use every instruction: N/S/E/W and the intercardinal moves (NE,
+// ...), turns by any whole number of degrees and F. All values are
+// positive, like in the puzzle input.
+
+const ORIGIN: Position = Position { east: 0, north: 0 };
+
+// the directions of the lines moves go along, both ways
+const LINES: [Position; 4] = [
+    Position { east: 1, north: 0 },
+    Position { east: 0, north: 1 },
+    Position { east: 1, north: 1 },
+    Position { east: 1, north: -1 },
+];
+
+fn difference(to: Position, from: Position) -> Position {
+    Position::new(to.east - from.east, to.north - from.north)
+}
+
+fn cross(a: Position, b: Position) -> i128 {
+    a.east as i128 * b.north as i128 - a.north as i128 * b.east as i128
+}
+
+// move `value` along one axis, always with a positive value
+fn axis_move(value: i64, positive: Heading, negative: Heading) -> Option<Action> {
+    match value {
+        0 => None,
+        v if v > 0 => Some(Action::Move(positive, v)),
+        v => Some(Action::Move(negative, -v)),
+    }
+}
+
+// one move along each axis
+fn axis_moves(offset: Position) -> Vec<Action> {
+    [
+        axis_move(offset.east, Heading::East, Heading::West),
+        axis_move(offset.north, Heading::North, Heading::South),
+    ]
+    .iter()
+    .flatten()
+    .copied()
+    .collect()
+}
+
+// the shorter way to turn by `degrees` clockwise, `None` for no turn
+fn turn(degrees: i64) -> Option<Action> {
+    match degrees.rem_euclid(360) {
+        0 => None,
+        d if d <= 180 => Some(Action::Turn(TurnDirection::Right, d)),
+        d => Some(Action::Turn(TurnDirection::Left, 360 - d)),
+    }
+}
+
+// The smallest distance going exactly `offset` along `bearing`. Both
+// coordinates are rounded by at most half a unit, so it is within one of the
+// length of `offset`, a little more for lengths f64 can't store exactly.
+fn distance(bearing: Bearing, offset: Position) -> Option<i64> {
+    let length = (offset.east as f64).hypot(offset.north as f64);
+    let slack = 2 + (length / (1u64 << 50) as f64) as i64;
+    let around = length.round() as i64;
+
+    (around.saturating_sub(slack).max(1)..=around.saturating_add(slack))
+        .find(|value| bearing.offset(*value) == (offset.east, offset.north))
+}
+
+// a single move by exactly `offset`
+fn move_by(offset: Position) -> Option<Action> {
+    COMPASS.iter().find_map(|heading| {
+        distance(Bearing::from(*heading), offset).map(|value| Action::Move(*heading, value))
+    })
+}
+
+// Every action either moves the ship or turns it, so the shortest plan is a
+// single F or move if the target lies along the heading or a compass point,
+// otherwise a move along each axis. A move along the current heading is
+// written as F. A heading to reach adds a turn, turning first pays off when
+// the target lies along the new heading.
+pub fn plan_ship(start: &Ship, target: Position, heading: Option<Bearing>) -> Vec<Action> {
+    let offset = difference(target, start.position);
+    let forward = |bearing: Bearing| distance(bearing, offset).map(Action::Forward);
+    let heading = heading.filter(|heading| *heading != start.heading);
+    let turn_to = heading.and_then(|heading| turn(heading.degrees() - start.heading.degrees()));
+
+    let moves: Vec<Action> = if offset == ORIGIN {
+        Vec::new()
+    } else if let Some(action) = forward(start.heading).or_else(|| move_by(offset)) {
+        vec![action]
+    } else if let Some(action) = heading.and_then(forward) {
+        // only ever used after turning
+        return turn_to.into_iter().chain(Some(action)).collect();
+    } else {
+        axis_moves(offset)
+            .into_iter()
+            .map(|action| match action {
+                Action::Move(h, value) if Bearing::from(h) == start.heading => {
+                    Action::Forward(value)
+                }
+                action => action,
+            })
+            .collect()
+    };
+
+    moves.into_iter().chain(turn_to).collect()
+}
+
+// how often `waypoint` fits into `offset`, if it does a whole positive
+// number of times
+fn times(offset: Position, waypoint: Position) -> Option<i64> {
+    let along = |offset: i64, waypoint: i64| match (offset, waypoint) {
+        (0, 0) => Some(None),
+        (_, 0) => None,
+        (o, w) if o % w == 0 && o / w > 0 => Some(Some(o / w)),
+        _ => None,
+    };
+
+    match (
+        along(offset.east, waypoint.east)?,
+        along(offset.north, waypoint.north)?,
+    ) {
+        (Some(a), Some(b)) if a == b => Some(a),
+        (Some(k), None) | (None, Some(k)) => Some(k),
+        _ => None,
+    }
+}
+
+// How often to use F with `a` and then with `b` to move the ship by
+// `offset`, both at least once.
+fn split(offset: Position, a: Position, b: Position) -> Option<(i64, i64)> {
+    let denominator = cross(a, b);
+    let (first, second) = if denominator != 0 {
+        let (first, second) = (cross(offset, b), cross(a, offset));
+        if first % denominator != 0 || second % denominator != 0 {
+            return None;
+        }
+        (first / denominator, second / denominator)
+    } else {
+        // both on one line, the offset has to be on it too. Along that line
+        // it's x * first + y * second = d, with all solutions
+        // first + t * y / gcd and second - t * x / gcd.
+        if a == ORIGIN || b == ORIGIN || cross(a, offset) != 0 {
+            return None;
+        }
+        let unit = (a.east as i128).gcd(&(a.north as i128));
+        let along = |p: Position| {
+            if a.east != 0 {
+                p.east as i128 * unit / a.east as i128
+            } else {
+                p.north as i128 * unit / a.north as i128
+            }
+        };
+        let (x, y, d) = (unit, along(b), along(offset));
+
+        let mut gcd = x.extended_gcd(&y);
+        if gcd.gcd < 0 {
+            gcd.gcd = -gcd.gcd;
+            gcd.x = -gcd.x;
+            gcd.y = -gcd.y;
+        }
+        if d % gcd.gcd != 0 {
+            return None;
+        }
+        let first = gcd.x.checked_mul(d / gcd.gcd)?;
+        let second = gcd.y.checked_mul(d / gcd.gcd)?;
+        let (step_first, step_second) = (y / gcd.gcd, x / gcd.gcd);
+
+        // second >= 1 limits t from above, first >= 1 from below or above
+        let most = Integer::div_floor(&(second - 1), &step_second);
+        let t = if step_first > 0 {
+            let least = -Integer::div_floor(&(first - 1), &step_first);
+            if least > most {
+                return None;
+            }
+            least
+        } else {
+            most.min(Integer::div_floor(&(first - 1), &-step_first))
+        };
+        (
+            first.checked_add(t.checked_mul(step_first)?)?,
+            second.checked_sub(t.checked_mul(step_second)?)?,
+        )
+    };
+
+    if first < 1 || second < 1 {
+        return None;
+    }
+    Some((i64::try_from(first).ok()?, i64::try_from(second).ok()?))
+}
+
+// where the line through `p` along `u` crosses the one through `q` along `v`
+fn meet(p: Position, u: Position, q: Position, v: Position) -> Option<Position> {
+    let denominator = cross(u, v);
+    let (east, north) = (
+        q.east as i128 - p.east as i128,
+        q.north as i128 - p.north as i128,
+    );
+    let numerator = east * v.north as i128 - north * v.east as i128;
+    if denominator == 0 || numerator % denominator != 0 {
+        return None;
+    }
+    let s = numerator / denominator;
+    Some(Position::new(
+        i64::try_from(p.east as i128 + s * u.east as i128).ok()?,
+        i64::try_from(p.north as i128 + s * u.north as i128).ok()?,
+    ))
+}
+
+// the waypoint after every single turn
+fn turned(ship: &WaypointShip) -> Vec<Position> {
+    (1..360)
+        .map(|degrees| {
+            let mut ship = *ship;
+            ship.turn(degrees).unwrap();
+            ship.waypoint
+        })
+        .collect()
+}
+
+// Every waypoint which might turn into `waypoint`. A turn rounds by at most
+// half a unit, so they are next to `waypoint` turned back.
+fn unturned(waypoint: Position) -> Vec<Position> {
+    let mut result = Vec::new();
+    for degrees in 1..360 {
+        let back = waypoint.rotate(-degrees);
+        for east in -1..=1 {
+            for north in -1..=1 {
+                result.push(Position::new(back.east + east, back.north + north));
+            }
+        }
+    }
+    result
+}
+
+// The fewest actions turning the waypoint into `to`: nothing, a single move
+// or turn, or a move along each axis. They are executed on `ship`, turns in
+// a row add up, so they depend on the turns before.
+fn fix_waypoint(ship: &mut WaypointShip, to: Position) -> Vec<Action> {
+    let offset = difference(to, ship.waypoint);
+    if offset == ORIGIN {
+        return Vec::new();
+    }
+
+    let single = move_by(offset).or_else(|| {
+        (1..360).filter_map(turn).find(|action| {
+            let mut turned = *ship;
+            turned.execute(action).is_ok() && turned.waypoint == to
+        })
+    });
+    let plan = match single {
+        Some(action) => vec![action],
+        None => axis_moves(offset),
+    };
+    for action in plan.iter() {
+        ship.execute(action).unwrap();
+    }
+    plan
+}
+
+// The plan using F with each waypoint in `stops` in turn, fixing the
+// waypoint before each of them and at the end. `None` if the stops don't
+// lead to `target`.
+fn plan_stops(
+    start: &WaypointShip,
+    target: Position,
+    waypoint: Option<Position>,
+    stops: &[Position],
+) -> Option<Vec<Action>> {
+    let offset = difference(target, start.position);
+    let counts = match stops {
+        [] if offset == ORIGIN => Vec::new(),
+        [stop] => vec![times(offset, *stop)?],
+        [a, b] => {
+            let (first, second) = split(offset, *a, *b)?;
+            vec![first, second]
+        }
+        _ => return None,
+    };
+
+    let mut ship = *start;
+    let mut plan = Vec::new();
+    for (stop, count) in stops.iter().zip(counts) {
+        plan.extend(fix_waypoint(&mut ship, *stop));
+        // the ship has to stay inside i64 on the way
+        count
+            .checked_mul(stop.east)
+            .and_then(|east| ship.position.east.checked_add(east))?;
+        count
+            .checked_mul(stop.north)
+            .and_then(|north| ship.position.north.checked_add(north))?;
+        ship.forward(count).unwrap();
+        plan.push(Action::Forward(count));
+    }
+    if let Some(waypoint) = waypoint {
+        plan.extend(fix_waypoint(&mut ship, waypoint));
+    }
+    Some(plan)
+}
+
+// The waypoints F can be used with once to move the ship by `offset`, see
+// `plan_waypoint`.
+fn single_stops(
+    start: &WaypointShip,
+    offset: Position,
+    waypoint: Option<Position>,
+) -> Vec<Position> {
+    let mut stops = vec![start.waypoint, offset];
+    stops.extend(waypoint);
+    for line in LINES.iter() {
+        stops.extend(meet(start.waypoint, *line, ORIGIN, offset));
+        stops.extend(waypoint.and_then(|w| meet(w, *line, ORIGIN, offset)));
+    }
+    stops.extend(turned(start));
+    stops.extend(waypoint.map(unturned).unwrap_or_default());
+
+    let mut result: Vec<Position> = Vec::new();
+    for stop in stops {
+        if times(offset, stop).is_some() && !result.contains(&stop) {
+            result.push(stop);
+        }
+    }
+    result
+}
+
+// The pairs of waypoints F can be used with one after the other to move the
+// ship by `offset`, ending with `waypoint`, see `plan_waypoint`.
+fn stop_pairs(start: &WaypointShip, offset: Position, waypoint: Position) -> Vec<[Position; 2]> {
+    let from = start.waypoint;
+
+    // one action away from the start waypoint and one from the target one
+    let mut middle = turned(start);
+    middle.extend(
+        unturned(waypoint)
+            .into_iter()
+            .filter(|m| move_by(difference(*m, from)).is_some()),
+    );
+    for u in LINES.iter() {
+        for v in LINES.iter() {
+            middle.extend(meet(from, *u, waypoint, *v));
+        }
+    }
+    // When both waypoints are on one line along `u`, every point of the line
+    // is one move away from both. With offset = s * from + t * u, using F once
+    // with a point of the line and the rest with the other waypoint works.
+    let line = LINES
+        .iter()
+        .find(|u| cross(difference(waypoint, from), **u) == 0 && cross(from, **u) != 0);
+    if let Some(u) = line {
+        let denominator = cross(from, *u);
+        let (s, t) = (cross(offset, *u), cross(from, offset));
+        if s % denominator == 0 && t % denominator == 0 && s / denominator >= 2 {
+            let (s, t) = (s / denominator, t / denominator);
+            // waypoint = from + e * u, every line goes one unit east or north
+            let e = if u.east != 0 {
+                (waypoint.east - from.east) as i128
+            } else {
+                (waypoint.north - from.north) as i128
+            };
+            let point = |along: i128| {
+                Some(Position::new(
+                    i64::try_from(from.east as i128 + along * u.east as i128).ok()?,
+                    i64::try_from(from.north as i128 + along * u.north as i128).ok()?,
+                ))
+            };
+            // F once with the point, s - 1 times with the other waypoint
+            middle.extend(
+                (s - 1)
+                    .checked_mul(e)
+                    .and_then(|x| point(t.checked_sub(x)?)),
+            );
+            middle.extend(point(t));
+        }
+    }
+
+    let mut pairs = vec![[from, waypoint]];
+    for m in middle {
+        pairs.push([m, waypoint]);
+        pairs.push([from, m]);
+    }
+    pairs.retain(|[a, b]| a != b && split(offset, *a, *b).is_some());
+    pairs
+}
+
+// The ship only moves with F, by a multiple of the waypoint. Setting the
+// waypoint to the offset to the target, F1 and setting the target waypoint
+// takes at most five actions, so a shorter plan uses F at most twice: once
+// with at most one action before or after it, or twice with one action
+// between them and at most one more. Each F uses the start or target
+// waypoint, one a single action turns the start waypoint into or the target
+// waypoint comes from, or the offset itself. Plans for all of those are
+// tried, the shortest one wins.
+pub fn plan_waypoint(
+    start: &WaypointShip,
+    target: Position,
+    waypoint: Option<Position>,
+) -> Vec<Action> {
+    let offset = difference(target, start.position);
+
+    let mut candidates: Vec<Vec<Position>> = vec![Vec::new()];
+    candidates.extend(
+        single_stops(start, offset, waypoint)
+            .into_iter()
+            .map(|stop| vec![stop]),
+    );
+    if let Some(waypoint) = waypoint {
+        candidates.extend(
+            stop_pairs(start, offset, waypoint)
+                .iter()
+                .map(|pair| pair.to_vec()),
+        );
+    }
+
+    // F with the offset itself always works
+    candidates
+        .iter()
+        .filter_map(|stops| plan_stops(start, target, waypoint, stops))
+        .min_by_key(|plan| plan.len())
+        .unwrap()
+}
+
+// one action per line, like the puzzle input
+pub fn to_instructions(actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(|action| format!("{}\n", action))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Navigator;
+    use test_case::test_case;
+
+    #[test_case(Position::new(17, -8), None, "E17\nF8\n")]
+    #[test_case(Position::new(0, 0), Some(180), ""; "nothing to do")]
+    #[test_case(Position::new(-3, 5), Some(270), "W3\nN5\nR90\n")]
+    #[test_case(Position::new(4, 0), Some(45), "E4\nL135\n")]
+    #[test_case(Position::new(-3, -3), None, "SW4\n"; "diagonal move")]
+    #[test_case(Position::new(-3, -3), Some(225), "SW4\nR45\n"; "move then turn")]
+    #[test_case(Position::new(0, -5), Some(90), "F5\nL90\n"; "forward then turn")]
+    fn test_plan_ship(target: Position, heading: Option<i64>, expected: &str) {
+        let start = Ship::new(Position::new(0, 0), Bearing::new(180));
+        let heading = heading.map(Bearing::new);
+        let plan = plan_ship(&start, target, heading);
+
+        assert_eq!(to_instructions(&plan), expected);
+
+        let mut ship = start;
+        assert_eq!(ship.navigate(&plan), Ok(target));
+        if let Some(heading) = heading {
+            assert_eq!(ship.heading, heading);
+        }
+    }
+
+    #[test_case(Position::new(0, 0), None, 0; "nothing to do")]
+    #[test_case(Position::new(30, 3), None, 1; "forward")]
+    #[test_case(Position::new(-30, -3), None, 2; "turn around")]
+    #[test_case(Position::new(3, -30), None, 2; "turn right")]
+    #[test_case(Position::new(30, 12), None, 2; "adjust north")]
+    #[test_case(Position::new(7, 1), None, 2; "adjust east")]
+    #[test_case(Position::new(7, 5), None, 3; "set both")]
+    #[test_case(Position::new(0, 5), None, 2; "north only")]
+    #[test_case(Position::new(30, 3), Some(Position::new(10, 1)), 1; "keep waypoint")]
+    #[test_case(Position::new(30, 3), Some(Position::new(-1, 10)), 2; "waypoint after")]
+    #[test_case(Position::new(20, 20), Some(Position::new(10, 10)), 2; "waypoint before")]
+    #[test_case(Position::new(20, 4), Some(Position::new(15, 1)), 3; "waypoint in between")]
+    #[test_case(Position::new(20, 3), Some(Position::new(10, 2)), 3; "waypoint fixed between stops")]
+    #[test_case(Position::new(7, 7), None, 2; "diagonal waypoint")]
+    #[test_case(Position::new(2, -20), Some(Position::new(1, -10)), 2; "turned waypoint")]
+    fn test_plan_waypoint(target: Position, waypoint: Option<Position>, length: usize) {
+        let start = WaypointShip::default();
+        let plan = plan_waypoint(&start, target, waypoint);
+
+        assert_eq!(plan.len(), length, "{}", to_instructions(&plan));
+
+        let mut ship = start;
+        assert_eq!(ship.navigate(&plan), Ok(target));
+        if let Some(waypoint) = waypoint {
+            assert_eq!(ship.waypoint, waypoint);
+        }
+    }
+
+    #[test]
+    fn test_instructions_round_trip() {
+        let plan = plan_waypoint(&WaypointShip::default(), Position::new(7, 5), None);
+        let parsed: Vec<Action> = to_instructions(&plan)
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect();
+
+        assert_eq!(parsed, plan);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{Action, Navigator, Orientation, Position};
+use simple_error::SimpleError;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrackPoint {
+    // the action which led here, `None` for the start
+    pub action: Option<Action>,
+    pub position: Position,
+    pub orientation: Orientation,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrackStats {
+    // manhattan distance of the point furthest from the start
+    pub max_distance: i64,
+    // south west and north east corner
+    pub bounding_box: (Position, Position),
+    // sum of the manhattan distances of all moves
+    pub travelled: i64,
+}
+
+// Every step of a voyage, starting with where the ship started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    points: Vec<TrackPoint>,
+}
+
+impl Track {
+    pub fn record(navigator: &mut impl Navigator, actions: &[Action]) -> Result<Self, SimpleError> {
+        let mut points = Vec::with_capacity(actions.len() + 1);
+        points.push(TrackPoint {
+            action: None,
+            position: navigator.position(),
+            orientation: navigator.orientation(),
+        });
+
+        navigator.navigate_with(actions, &mut |navigator, action| {
+            points.push(TrackPoint {
+                action: Some(*action),
+                position: navigator.position(),
+                orientation: navigator.orientation(),
+            })
+        })?;
+
+        Ok(Track { points })
+    }
+
+    pub fn points(&self) -> &[TrackPoint] {
+        &self.points
+    }
+
+    pub fn start(&self) -> Position {
+        self.points[0].position
+    }
+
+    pub fn end(&self) -> Position {
+        self.points[self.points.len() - 1].position
+    }
+
+    pub fn stats(&self) -> TrackStats {
+        let start = self.start();
+        let mut stats = TrackStats {
+            max_distance: 0,
+            bounding_box: (start, start),
+            travelled: 0,
+        };
+
+        for pair in self.points.windows(2) {
+            stats.travelled += pair[0].position.manhattan_distance(&pair[1].position);
+        }
+
+        for point in self.points.iter() {
+            let position = point.position;
+            stats.max_distance = stats.max_distance.max(start.manhattan_distance(&position));
+
+            let (min, max) = &mut stats.bounding_box;
+            min.east = min.east.min(position.east);
+            min.north = min.north.min(position.north);
+            max.east = max.east.max(position.east);
+            max.north = max.north.max(position.north);
+        }
+
+        stats
+    }
+
+    // Points where the ship actually moved, without the repeats turns and
+    // waypoint moves leave in the track.
+    fn path(&self) -> Vec<Position> {
+        let mut path: Vec<Position> = Vec::new();
+        for point in self.points.iter() {
+            if path.last() != Some(&point.position) {
+                path.push(point.position);
+            }
+        }
+        path
+    }
+
+    // The path as a GeoJSON feature with a LineString, east as x and north
+    // as y. The coordinates are the puzzle units, not longitude / latitude.
+    // A LineString needs two positions, a ship which never moved is a Point.
+    pub fn to_geojson(&self) -> String {
+        let path = self.path();
+        let coordinates: Vec<String> = path
+            .iter()
+            .map(|p| format!("[{},{}]", p.east, p.north))
+            .collect();
+
+        let geometry = if path.len() < 2 {
+            format!("{{\"type\":\"Point\",\"coordinates\":{}}}", coordinates[0])
+        } else {
+            format!(
+                "{{\"type\":\"LineString\",\"coordinates\":[{}]}}",
+                coordinates.join(",")
+            )
+        };
+
+        format!(
+            "{{\"type\":\"Feature\",\"properties\":{{}},\"geometry\":{}}}",
+            geometry
+        )
+    }
+
+    // The path as a SVG polyline, north is up. The start is marked green,
+    // the end red.
+    pub fn to_svg(&self) -> String {
+        Svg(self).to_string()
+    }
+}
+
+struct Svg<'a>(&'a Track);
+
+impl fmt::Display for Svg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let track = self.0;
+        let (min, max) = track.stats().bounding_box;
+        let margin = ((max.east - min.east).max(max.north - min.north) / 20).max(1);
+        let path = track.path();
+
+        // SVG y grows downwards
+        let point = |p: &Position| format!("{},{}", p.east, -p.north);
+        let points: Vec<String> = path.iter().map(point).collect();
+
+        writeln!(
+            f,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+            min.east - margin,
+            -max.north - margin,
+            max.east - min.east + 2 * margin,
+            max.north - min.north + 2 * margin
+        )?;
+        writeln!(
+            f,
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"black\" \
+             vector-effect=\"non-scaling-stroke\"/>",
+            points.join(" ")
+        )?;
+        for (position, color) in [(track.start(), "green"), (track.end(), "red")].iter() {
+            writeln!(
+                f,
+                "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                position.east, -position.north, margin, color
+            )?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bearing, Heading, Ship, TurnDirection, WaypointShip};
+
+    fn actions() -> Vec<Action> {
+        ["F10", "N3", "F7", "R90", "F11"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_record() {
+        let track = Track::record(&mut Ship::default(), &actions()).unwrap();
+
+        assert_eq!(track.points().len(), 6);
+        assert_eq!(
+            track.points()[4],
+            TrackPoint {
+                action: Some(Action::Turn(TurnDirection::Right, 90)),
+                position: Position::new(17, 3),
+                orientation: Orientation::Heading(Bearing::from(Heading::South)),
+            }
+        );
+        assert_eq!(track.end(), Position::new(17, -8));
+        assert_eq!(
+            track.stats(),
+            TrackStats {
+                max_distance: 25,
+                bounding_box: (Position::new(0, -8), Position::new(17, 3)),
+                travelled: 31,
+            }
+        );
+    }
+
+    #[test]
+    fn test_record_waypoint() {
+        let track = Track::record(&mut WaypointShip::default(), &actions()).unwrap();
+
+        assert_eq!(
+            track.points()[2].orientation,
+            Orientation::Waypoint(Position::new(10, 4))
+        );
+        assert_eq!(track.stats().max_distance, 286);
+        assert_eq!(track.stats().travelled, 110 + 98 + 154);
+    }
+
+    #[test]
+    fn test_to_geojson() {
+        let track = Track::record(&mut Ship::default(), &actions()).unwrap();
+
+        assert_eq!(
+            track.to_geojson(),
+            "{\"type\":\"Feature\",\"properties\":{},\"geometry\":{\"type\":\"LineString\",\
+             \"coordinates\":[[0,0],[10,0],[10,3],[17,3],[17,-8]]}}"
+        );
+    }
+
+    #[test]
+    fn test_to_geojson_without_moving() {
+        let turns: Vec<Action> = ["R90", "L180"].iter().map(|s| s.parse().unwrap()).collect();
+        let expected = "{\"type\":\"Feature\",\"properties\":{},\"geometry\":\
+                        {\"type\":\"Point\",\"coordinates\":[0,0]}}";
+
+        let track = Track::record(&mut Ship::default(), &turns).unwrap();
+        assert_eq!(track.to_geojson(), expected);
+
+        let track = Track::record(&mut Ship::default(), &[]).unwrap();
+        assert_eq!(track.to_geojson(), expected);
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let track = Track::record(&mut Ship::default(), &actions()).unwrap();
+
+        assert_eq!(
+            track.to_svg(),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -4 19 13\">\n  \
+             <polyline points=\"0,0 10,0 10,-3 17,-3 17,8\" fill=\"none\" stroke=\"black\" \
+             vector-effect=\"non-scaling-stroke\"/>\n  \
+             <circle cx=\"0\" cy=\"0\" r=\"1\" fill=\"green\"/>\n  \
+             <circle cx=\"17\" cy=\"8\" r=\"1\" fill=\"red\"/>\n\
+             </svg>\n"
+        );
+    }
+}
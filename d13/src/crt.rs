@@ -0,0 +1,162 @@
+use num_integer::Integer;
+use num_traits::{CheckedAdd, CheckedMul, Signed};
+use simple_error::SimpleError;
+use std::fmt;
+
+// Integers the solver works with, like `i128` or `num_bigint::BigInt`.
+// Fixed width types report an error when a result doesn't fit.
+pub trait CrtInteger: Integer + Signed + Clone + CheckedAdd + CheckedMul + fmt::Display {}
+
+impl<T> CrtInteger for T where T: Integer + Signed + Clone + CheckedAdd + CheckedMul + fmt::Display {}
+
+// x ≡ residue (mod modulus)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Congruence<T> {
+    pub residue: T,
+    pub modulus: T,
+}
+
+impl<T> Congruence<T> {
+    pub fn new(residue: T, modulus: T) -> Self {
+        Congruence { residue, modulus }
+    }
+}
+
+// All solutions are `value + n * period`, `value` is the smallest one which
+// is not negative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<T> {
+    pub value: T,
+    pub period: T,
+}
+
+fn overflow() -> SimpleError {
+    SimpleError::new("overflow")
+}
+
+// the inverse of `a` modulo `m`, they have to be coprime
+fn inverse<T: CrtInteger>(a: &T, m: &T) -> T {
+    a.extended_gcd(m).x.mod_floor(m)
+}
+
+// Solves the congruences one after another with the generalized chinese
+// remainder theorem: the moduli don't need to be coprime, the period is
+// their least common multiple. Fails when two congruences contradict each
+// other.
+pub fn solve<T: CrtInteger>(congruences: &[Congruence<T>]) -> Result<Solution<T>, SimpleError> {
+    let mut value = T::zero();
+    let mut period = T::one();
+
+    for congruence in congruences {
+        let modulus = &congruence.modulus;
+        if !modulus.is_positive() {
+            return Err(SimpleError::new(format!(
+                "modulus must be positive: {}",
+                modulus
+            )));
+        }
+        let residue = congruence.residue.mod_floor(modulus);
+
+        // value + period * k ≡ residue (mod modulus) needs a k, which only
+        // exists if the difference is a multiple of their gcd
+        let gcd = period.gcd(modulus);
+        let difference = residue.clone() - value.clone();
+        if !difference.is_multiple_of(&gcd) {
+            return Err(SimpleError::new(format!(
+                "no solution: x ≡ {} (mod {}) contradicts the congruences before it",
+                residue, modulus
+            )));
+        }
+
+        let step = modulus.clone() / gcd.clone();
+        let k = (difference / gcd.clone())
+            .mod_floor(&step)
+            .checked_mul(&inverse(&(period.clone() / gcd).mod_floor(&step), &step))
+            .ok_or_else(overflow)?
+            .mod_floor(&step);
+
+        value = period
+            .checked_mul(&k)
+            .and_then(|offset| value.checked_add(&offset))
+            .ok_or_else(overflow)?;
+        period = period.checked_mul(&step).ok_or_else(overflow)?;
+        value = value.mod_floor(&period);
+    }
+
+    Ok(Solution { value, period })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+
+    fn congruences(input: &[(i128, i128)]) -> Vec<Congruence<i128>> {
+        input
+            .iter()
+            .map(|(residue, modulus)| Congruence::new(*residue, *modulus))
+            .collect()
+    }
+
+    #[test]
+    fn test_coprime() {
+        assert_eq!(
+            solve(&congruences(&[(2, 3), (3, 5), (2, 7)])),
+            Ok(Solution {
+                value: 23,
+                period: 105
+            })
+        );
+    }
+
+    #[test]
+    fn test_not_coprime() {
+        assert_eq!(
+            solve(&congruences(&[(1, 4), (3, 6), (-3, 9)])),
+            Ok(Solution {
+                value: 33,
+                period: 36
+            })
+        );
+        assert_eq!(
+            solve(&congruences(&[(0, 4), (1, 6)])),
+            Err(SimpleError::new(
+                "no solution: x ≡ 1 (mod 6) contradicts the congruences before it"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_modulus() {
+        assert_eq!(
+            solve(&congruences(&[(0, 4), (1, 0)])),
+            Err(SimpleError::new("modulus must be positive: 0"))
+        );
+        assert_eq!(
+            solve::<i128>(&[]),
+            Ok(Solution {
+                value: 0,
+                period: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        // two mersenne primes, their product needs more than 128 bits
+        let primes: [i128; 2] = [(1 << 61) - 1, (1 << 89) - 1];
+        let small: Vec<Congruence<i128>> = primes.iter().map(|p| Congruence::new(1, *p)).collect();
+        assert_eq!(solve(&small), Err(SimpleError::new("overflow")));
+
+        let big: Vec<Congruence<BigInt>> = primes
+            .iter()
+            .map(|p| Congruence::new(BigInt::from(1), BigInt::from(*p)))
+            .collect();
+        let solution = solve(&big).unwrap();
+        assert_eq!(solution.value, BigInt::from(1));
+        assert_eq!(
+            solution.period,
+            primes.iter().map(|p| BigInt::from(*p)).product::<BigInt>()
+        );
+    }
+}
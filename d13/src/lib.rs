@@ -0,0 +1,7 @@
+mod crt;
+mod schedule;
+mod timetable;
+
+pub use crt::{solve, Congruence, CrtInteger, Solution};
+pub use schedule::{Constraint, Schedule};
+pub use timetable::{next_departure, Departure, Timetable};
@@ -1,83 +1,34 @@
+use d13::{solve, Schedule};
+use num_bigint::BigInt;
 use simple_error::SimpleError;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 fn main() {
-    let file = File::open("input.txt").unwrap();
+    let schedule: Schedule = fs::read_to_string("input.txt").unwrap().parse().unwrap();
 
-    let input: Vec<String> = BufReader::new(file)
-        .lines()
-        .map(|line| line.unwrap())
-        .collect();
-
-    let time: u64 = input[0].parse().unwrap();
-
-    let lines: Vec<u16> = input[1].split(",").filter_map(|s| s.parse().ok()).collect();
-
-    println!("part1: {:?}", run(time, &lines));
-
-    let lines2: Vec<&str> = input[1].split(",").collect();
-    println!("part2: {:?}", run_2(&lines2));
+    println!("part1: {:?}", run(&schedule));
+    println!("part2: {:?}", run_2(&schedule));
 }
 
-fn run(time: u64, buses: &[u16]) -> u64 {
-    let mut t = time;
-
-    loop {
-        for b in buses.iter().filter(|&b| t % ((*b) as u64) == 0) {
-            return (t - time) * (*b as u64);
-        }
-        t += 1;
-    }
-}
-
-fn chinese_remainder(input: &HashMap<i64, i64>) -> Option<i64> {
-    fn egcd(a: i64, b: i64) -> (i64, i64, i64) {
-        if a == 0 {
-            (b, 0, 1)
-        } else {
-            let (g, x, y) = egcd(b % a, a);
-            (g, y - (b / a) * x, x)
-        }
-    }
-
-    fn mod_inv(x: i64, n: i64) -> Option<i64> {
-        let (g, x, _) = egcd(x, n);
-        if g == 1 {
-            Some((x % n + n) % n)
-        } else {
-            None
-        }
-    }
-
-    // https://rosettacode.org/wiki/Chinese_remainder_theorem#Rust
-    let prod = input.values().product::<i64>();
-
-    let mut sum = 0;
-
-    for (&residue, &modulus) in input.iter() {
-        let p = prod / modulus;
-        sum += residue * mod_inv(p, modulus)? * p
-    }
-
-    Some(sum % prod)
+// wait time for the first bus departing at or after the earliest time,
+// times its ID
+fn run(schedule: &Schedule) -> Result<u64, SimpleError> {
+    let time = schedule.earliest();
+    let departure = schedule
+        .timetable()?
+        .earliest(time)
+        .ok_or_else(|| SimpleError::new("no bus departs"))?;
+
+    (departure.time - time)
+        .checked_mul(departure.bus)
+        .ok_or_else(|| SimpleError::new("overflow"))
 }
 
-fn run_2(input: &[&str]) -> Result<i64, SimpleError> {
-    let parsed_input: HashMap<i64, i64> = input
-        .iter()
-        .enumerate()
-        .map(|(i, v)| (i, v.parse().ok()))
-        .filter(|(_, v)| v.is_some())
-        .map(|(i, v)| (i as i64, v.unwrap()))
-        .collect();
-
-    if let Some(result) = chinese_remainder(&parsed_input) {
-        Ok(parsed_input.values().product::<i64>() - result)
-    } else {
-        Err(SimpleError::new("no value found"))
-    }
+// The earliest time where every bus departs at its offset: bus `i` in the
+// list `i` minutes later, unless the offset is given explicitly.
+// `BigInt` because a few large bus IDs already overflow an `i128`.
+fn run_2(schedule: &Schedule) -> Result<BigInt, SimpleError> {
+    Ok(solve(&schedule.congruences::<BigInt>())?.value)
 }
 
 #[cfg(test)]
@@ -87,16 +38,40 @@ mod tests {
 
     #[test]
     fn it_works() {
-        assert_eq!(run(939, &[7, 13, 59, 31, 19]), 295);
+        let schedule = "939\n7,13,x,x,59,x,31,19".parse().unwrap();
+        assert_eq!(run(&schedule), Ok(295));
+
+        let schedule = format!("1\n{}", 1u64 << 33).parse().unwrap();
+        assert_eq!(run(&schedule), Err(SimpleError::new("overflow")));
     }
 
-    #[test_case(&["17", "x", "13", "19"], 3_417)]
-    #[test_case(&["67", "7", "59", "61"], 754_018)]
-    #[test_case(&["67", "x", "7", "59", "61"], 779_210)]
-    #[test_case(&["7", "13", "x", "x", "59", "x", "31", "19"], 1_068_781)]
-    #[test_case(&["67", "7", "x", "59", "61"], 1_261_476)]
-    #[test_case(&["1789", "37", "47", "1889"], 1_202_161_486)]
-    fn it_works_2(input: &[&str], expected: i64) {
-        assert_eq!(run_2(&input), Ok(expected));
+    #[test_case("17,x,13,19", 3_417)]
+    #[test_case("67,7,59,61", 754_018)]
+    #[test_case("67,x,7,59,61", 779_210)]
+    #[test_case("7,13,x,x,59,x,31,19", 1_068_781)]
+    #[test_case("67,7,x,59,61", 1_261_476)]
+    #[test_case("1789,37,47,1889", 1_202_161_486)]
+    #[test_case("bus 17 at t, bus 13 at t+2, bus 19 at t+3", 3_417; "explicit")]
+    fn it_works_2(buses: &str, expected: i128) {
+        let schedule = format!("0\n{}", buses).parse().unwrap();
+        assert_eq!(run_2(&schedule), Ok(BigInt::from(expected)));
+    }
+
+    #[test]
+    fn it_works_2_large_buses() {
+        let schedule: Schedule = "0\n18446744073709551557,2305843009213693951,2147483647"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            solve(&schedule.congruences::<i128>()),
+            Err(SimpleError::new("overflow"))
+        );
+        assert_eq!(
+            run_2(&schedule),
+            Ok("77569003975871935328082679106386365380562056851"
+                .parse::<BigInt>()
+                .unwrap())
+        );
     }
 }
@@ -0,0 +1,207 @@
+use crate::{Congruence, CrtInteger, Timetable};
+use simple_error::SimpleError;
+use std::str::FromStr;
+
+// Bus `bus` has to depart `offset` minutes after t.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub bus: u64,
+    pub offset: i64,
+}
+
+// The puzzle input: the earliest time to leave on the first line, the buses
+// on the second. Every entry of the bus list is either
+// - `x`, a bus which is out of service,
+// - a bus ID, which has to depart at t + its position in the list,
+// - or an explicit constraint like `bus 13 at t+1` (also `t` and `t-N`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    earliest: u64,
+    constraints: Vec<Constraint>,
+}
+
+fn parse_bus(s: &str) -> Result<u64, SimpleError> {
+    match s.parse() {
+        Ok(0) => Err(SimpleError::new("bus IDs must be positive")),
+        Ok(bus) => Ok(bus),
+        Err(_) => Err(SimpleError::new(format!("invalid bus ID: '{}'", s))),
+    }
+}
+
+// `t`, `t+N` or `t-N`
+fn parse_offset(s: &str) -> Result<i64, SimpleError> {
+    let invalid = || SimpleError::new(format!("invalid offset: '{}'", s));
+    match s.strip_prefix('t').map(str::trim) {
+        Some("") => Ok(0),
+        Some(rest) => {
+            let (sign, digits) = match rest.chars().next() {
+                Some(sign @ '+') | Some(sign @ '-') => (sign, rest[1..].trim()),
+                _ => return Err(invalid()),
+            };
+            // the sign is parsed with the digits, so t-9223372036854775808
+            // still fits. Only digits may follow it, not another sign.
+            if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            format!("{}{}", sign, digits)
+                .parse::<i64>()
+                .map_err(|_| invalid())
+        }
+        None => Err(invalid()),
+    }
+}
+
+// one entry of the bus list, `None` for `x`
+fn parse_entry(entry: &str, position: usize) -> Result<Option<Constraint>, SimpleError> {
+    if entry == "x" {
+        return Ok(None);
+    }
+    if let Some(rest) = entry.strip_prefix("bus ") {
+        let (bus, offset) = rest
+            .split_once(" at ")
+            .ok_or_else(|| SimpleError::new(format!("invalid constraint: '{}'", entry)))?;
+        return Ok(Some(Constraint {
+            bus: parse_bus(bus.trim())?,
+            offset: parse_offset(offset.trim())?,
+        }));
+    }
+    Ok(Some(Constraint {
+        bus: parse_bus(entry)?,
+        offset: position as i64,
+    }))
+}
+
+impl Schedule {
+    pub fn new(earliest: u64, constraints: Vec<Constraint>) -> Self {
+        Schedule {
+            earliest,
+            constraints,
+        }
+    }
+
+    pub fn from_lines<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+    ) -> Result<Self, SimpleError> {
+        let lines: Vec<S> = lines.into_iter().collect();
+        if lines.len() != 2 {
+            return Err(SimpleError::new(format!(
+                "expected 2 lines, found {}",
+                lines.len()
+            )));
+        }
+
+        let time = lines[0].as_ref().trim();
+        let earliest = time
+            .parse()
+            .map_err(|_| SimpleError::new(format!("line 1: invalid time: '{}'", time)))?;
+
+        let mut constraints = Vec::new();
+        for (i, entry) in lines[1].as_ref().split(',').enumerate() {
+            let constraint = parse_entry(entry.trim(), i)
+                .map_err(|err| SimpleError::new(format!("line 2, position {}: {}", i + 1, err)))?;
+            constraints.extend(constraint);
+        }
+
+        Ok(Schedule::new(earliest, constraints))
+    }
+
+    pub fn earliest(&self) -> u64 {
+        self.earliest
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    // every bus in service, in the order of the list
+    pub fn buses(&self) -> Vec<u64> {
+        self.constraints.iter().map(|c| c.bus).collect()
+    }
+
+    pub fn timetable(&self) -> Result<Timetable, SimpleError> {
+        Timetable::new(&self.buses())
+    }
+
+    // t + offset ≡ 0 (mod bus) for every constraint, as `i128` or a
+    // `BigInt` when the buses are too large for it
+    pub fn congruences<T>(&self) -> Vec<Congruence<T>>
+    where
+        T: CrtInteger + From<u64> + From<i64>,
+    {
+        self.constraints
+            .iter()
+            .map(|c| Congruence::new(-T::from(c.offset), T::from(c.bus)))
+            .collect()
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Schedule::from_lines(s.lines())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_parse() {
+        let schedule: Schedule = "939\n7,13,x,x,59,x,31,19\n".parse().unwrap();
+
+        assert_eq!(schedule.earliest(), 939);
+        assert_eq!(schedule.buses(), vec![7, 13, 59, 31, 19]);
+        assert_eq!(schedule.constraints()[2], Constraint { bus: 59, offset: 4 });
+    }
+
+    #[test]
+    fn test_parse_explicit() {
+        let schedule: Schedule = "0\nbus 7 at t+0, bus 13 at t+1,x, 59, bus 31 at t-2"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            schedule.constraints(),
+            &[
+                Constraint { bus: 7, offset: 0 },
+                Constraint { bus: 13, offset: 1 },
+                Constraint { bus: 59, offset: 3 },
+                Constraint {
+                    bus: 31,
+                    offset: -2
+                },
+            ]
+        );
+
+        let schedule: Schedule = "0\nbus 7 at t-9223372036854775808".parse().unwrap();
+        assert_eq!(schedule.constraints()[0].offset, i64::MIN);
+    }
+
+    #[test_case("939", "expected 2 lines, found 1")]
+    #[test_case("soon\n7", "line 1: invalid time: 'soon'")]
+    #[test_case("939\n7,y,13", "line 2, position 2: invalid bus ID: 'y'")]
+    #[test_case("939\n7,,13", "line 2, position 2: invalid bus ID: ''")]
+    #[test_case("939\n7,x,0", "line 2, position 3: bus IDs must be positive")]
+    #[test_case("939\n7,x,-13", "line 2, position 3: invalid bus ID: '-13'")]
+    #[test_case("939\nbus 7", "line 2, position 1: invalid constraint: 'bus 7'")]
+    #[test_case("939\nbus 7 at 1", "line 2, position 1: invalid offset: '1'")]
+    #[test_case("939\nbus 7 at t*1", "line 2, position 1: invalid offset: 't*1'")]
+    #[test_case("939\nbus 7 at t€1", "line 2, position 1: invalid offset: 't€1'"; "non ascii offset")]
+    #[test_case("939\nbus 7 at t+-5", "line 2, position 1: invalid offset: 't+-5'"; "two signs")]
+    #[test_case(
+        "939\nbus 7 at t--9223372036854775808",
+        "line 2, position 1: invalid offset: 't--9223372036854775808'";
+        "negated minimum"
+    )]
+    #[test_case(
+        "939\nbus 7 at t+9223372036854775808",
+        "line 2, position 1: invalid offset: 't+9223372036854775808'";
+        "too large"
+    )]
+    fn test_parse_errors(input: &str, expected: &str) {
+        assert_eq!(input.parse::<Schedule>(), Err(SimpleError::new(expected)));
+    }
+}
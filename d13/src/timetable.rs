@@ -0,0 +1,146 @@
+use crate::{solve, Congruence};
+use simple_error::SimpleError;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Departure {
+    pub time: u64,
+    pub bus: u64,
+}
+
+// Bus `id` departs at every multiple of `id`, starting at 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timetable {
+    buses: Vec<u64>,
+}
+
+// the first departure of `bus` at or after `time`, `None` if that doesn't
+// fit in an u64
+pub fn next_departure(bus: u64, time: u64) -> Option<u64> {
+    time.div_ceil(bus).checked_mul(bus)
+}
+
+impl Timetable {
+    pub fn new(buses: &[u64]) -> Result<Self, SimpleError> {
+        if buses.contains(&0) {
+            return Err(SimpleError::new("bus IDs must be positive"));
+        }
+        Ok(Timetable {
+            buses: buses.to_vec(),
+        })
+    }
+
+    pub fn buses(&self) -> &[u64] {
+        &self.buses
+    }
+
+    // the next departure of every bus at or after `time`, earliest first
+    pub fn next_departures(&self, time: u64) -> Vec<Departure> {
+        let mut departures: Vec<Departure> = self
+            .buses
+            .iter()
+            .filter_map(|bus| {
+                Some(Departure {
+                    time: next_departure(*bus, time)?,
+                    bus: *bus,
+                })
+            })
+            .collect();
+        departures.sort_unstable();
+        departures
+    }
+
+    pub fn earliest(&self, time: u64) -> Option<Departure> {
+        self.next_departures(time).first().copied()
+    }
+
+    // every departure inside `window`, earliest first
+    pub fn departures(&self, window: Range<u64>) -> Vec<Departure> {
+        let mut departures = Vec::new();
+        for bus in self.buses.iter() {
+            let mut time = next_departure(*bus, window.start);
+            while let Some(t) = time.filter(|t| *t < window.end) {
+                departures.push(Departure { time: t, bus: *bus });
+                time = t.checked_add(*bus);
+            }
+        }
+        departures.sort_unstable();
+        departures
+    }
+
+    // The earliest time at or after `time` where bus `a` departs and bus `b`
+    // departs `k` minutes later (or earlier for negative `k`). That's
+    // t ≡ 0 (mod a) and t ≡ -k (mod b), so the answers repeat every
+    // lcm(a, b) minutes.
+    pub fn apart(a: u64, b: u64, k: i64, time: u64) -> Result<u64, SimpleError> {
+        if a == 0 || b == 0 {
+            return Err(SimpleError::new("bus IDs must be positive"));
+        }
+        let solution = solve(&[
+            Congruence::new(0, a as i128),
+            Congruence::new(-(k as i128), b as i128),
+        ])?;
+
+        // smallest value + n * period which is not before `time`
+        let behind = (time as i128 - solution.value).max(0);
+        let periods = (behind + solution.period - 1) / solution.period;
+        let t = solution.value + periods * solution.period;
+        u64::try_from(t).map_err(|_| SimpleError::new("departure too late"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timetable() -> Timetable {
+        Timetable::new(&[7, 13, 59, 31, 19]).unwrap()
+    }
+
+    #[test]
+    fn test_next_departures() {
+        let departures = timetable().next_departures(939);
+
+        assert_eq!(departures[0], Departure { time: 944, bus: 59 });
+        assert_eq!(departures[1], Departure { time: 945, bus: 7 });
+        assert_eq!(departures.len(), 5);
+        assert_eq!(
+            timetable().earliest(944),
+            Some(Departure { time: 944, bus: 59 })
+        );
+        assert_eq!(next_departure(7, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_departures() {
+        assert_eq!(
+            timetable().departures(938..950),
+            vec![
+                Departure { time: 938, bus: 7 },
+                Departure { time: 944, bus: 59 },
+                Departure { time: 945, bus: 7 },
+                Departure { time: 949, bus: 13 },
+            ]
+        );
+        assert_eq!(timetable().departures(10..10), vec![]);
+    }
+
+    #[test]
+    fn test_apart() {
+        assert_eq!(Timetable::apart(17, 13, 2, 0), Ok(102));
+        assert_eq!(Timetable::apart(17, 13, 2, 103), Ok(102 + 221));
+        assert_eq!(Timetable::apart(17, 13, -2, 0), Ok(119));
+        assert_eq!(Timetable::apart(4, 6, 0, 13), Ok(24));
+        assert_eq!(
+            Timetable::apart(4, 6, 1, 0),
+            Err(SimpleError::new(
+                "no solution: x ≡ 5 (mod 6) contradicts the congruences before it"
+            ))
+        );
+        assert_eq!(
+            Timetable::new(&[7, 0]),
+            Err(SimpleError::new("bus IDs must be positive"))
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{check_mask, BitMask, Command, Decoder};
+use simple_error::SimpleError;
+use std::collections::HashMap;
+use std::fmt;
+
+// Runs docking programs, the decoder chip decides what the mask does.
+// Memory starts as all zeros, like the mask starts without any bits.
+#[derive(Debug, Clone)]
+pub struct DockingComputer<D> {
+    decoder: D,
+    mask: BitMask,
+    memory: HashMap<u64, u64>,
+}
+
+impl<D: Decoder> DockingComputer<D> {
+    pub fn new(decoder: D) -> Self {
+        DockingComputer {
+            decoder,
+            mask: BitMask::new(),
+            memory: HashMap::new(),
+        }
+    }
+
+    pub fn step(&mut self, command: &Command) -> Result<(), SimpleError> {
+        match command {
+            Command::SetMask(mask) => {
+                // the decoders check too, this reports it where it happens
+                check_mask(mask)?;
+                self.mask = mask.clone();
+            }
+            Command::SetValue(address, value) => {
+                let writes = self.decoder.decode(&self.mask, *address, *value)?;
+                self.memory.extend(writes);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run<'a>(
+        &mut self,
+        commands: impl IntoIterator<Item = &'a Command>,
+    ) -> Result<(), SimpleError> {
+        for (i, command) in commands.into_iter().enumerate() {
+            self.step(command)
+                .map_err(|err| SimpleError::new(format!("command {}: {}", i + 1, err)))?;
+        }
+        Ok(())
+    }
+
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    pub fn mask(&self) -> &BitMask {
+        &self.mask
+    }
+
+    // only the addresses which were written to
+    pub fn memory(&self) -> &HashMap<u64, u64> {
+        &self.memory
+    }
+
+    pub fn get(&self, address: u64) -> u64 {
+        self.memory.get(&address).copied().unwrap_or(0)
+    }
+
+    // the addresses which were written to, sorted by address
+    pub fn entries(&self) -> Vec<(u64, u64)> {
+        let mut entries: Vec<(u64, u64)> = self.memory.iter().map(|(a, v)| (*a, *v)).collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    // u128 because many big values add up to more than u64 holds
+    pub fn sum(&self) -> u128 {
+        self.memory.values().map(|v| *v as u128).sum()
+    }
+
+    // The memory without zeros as a program, one `mem[a] = v` per line.
+    // Running it with a fresh computer restores the memory.
+    pub fn dump(&self) -> String {
+        Dump(self.entries()).to_string()
+    }
+}
+
+// sorted memory entries
+struct Dump(Vec<(u64, u64)>);
+
+impl fmt::Display for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (address, value) in self.0.iter() {
+            if *value != 0 {
+                writeln!(f, "mem[{}] = {}", address, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddressDecoder, ValueDecoder};
+
+    fn program(lines: &[&str]) -> Vec<Command> {
+        lines.iter().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_step() {
+        let program = program(&[
+            "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X",
+            "mem[8] = 11",
+            "mem[7] = 101",
+            "mem[8] = 0",
+        ]);
+        let mut computer = DockingComputer::new(ValueDecoder);
+
+        computer.step(&program[0]).unwrap();
+        assert_eq!(computer.mask().len(), 36);
+        assert_eq!(computer.get(8), 0);
+
+        computer.step(&program[1]).unwrap();
+        assert_eq!(computer.get(8), 73);
+
+        computer.run(&program[2..]).unwrap();
+        assert_eq!(computer.entries(), vec![(7, 101), (8, 64)]);
+        assert_eq!(computer.sum(), 165);
+    }
+
+    #[test]
+    fn test_dump() {
+        let commands = program(&[
+            "mask = 000000000000000000000000000000X1001X",
+            "mem[42] = 100",
+            "mask = 00000000000000000000000000000000X0XX",
+            "mem[26] = 1",
+        ]);
+        let mut computer = DockingComputer::new(AddressDecoder::default());
+        computer.run(&commands).unwrap();
+
+        assert_eq!(computer.memory().len(), 10);
+        assert_eq!(computer.sum(), 208);
+        assert_eq!(
+            computer.dump(),
+            "mem[16] = 1\nmem[17] = 1\nmem[18] = 1\nmem[19] = 1\nmem[24] = 1\n\
+             mem[25] = 1\nmem[26] = 1\nmem[27] = 1\nmem[58] = 100\nmem[59] = 100\n"
+        );
+
+        let dump = computer.dump();
+        let mut restored = DockingComputer::new(ValueDecoder);
+        restored
+            .run(&program(&dump.lines().collect::<Vec<&str>>()))
+            .unwrap();
+        assert_eq!(restored.entries(), computer.entries());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut computer = DockingComputer::new(AddressDecoder::default());
+        let long_mask = format!("mask = {}", "0".repeat(65));
+
+        assert_eq!(
+            computer.run(&program(&["mem[1] = 2", &long_mask])),
+            Err(SimpleError::new(
+                "command 2: mask has 65 bits, at most 64 are supported"
+            ))
+        );
+        assert_eq!(computer.get(1), 2);
+    }
+
+    #[test]
+    fn test_sum_beyond_u64() {
+        let mut computer = DockingComputer::new(ValueDecoder);
+        let mask = format!("mask = {}", "X".repeat(64));
+        let max = format!("mem[1] = {}", u64::MAX);
+
+        computer
+            .run(&program(&[&mask, &max, "mem[2] = 1"]))
+            .unwrap();
+        assert_eq!(computer.sum(), u64::MAX as u128 + 1);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::BitMask;
+use simple_error::SimpleError;
+
+// Every `X` in an address mask doubles the number of writes, by default
+// more than this are rejected so a bad mask can't write millions of addresses.
+pub const DEFAULT_MAX_FLOATING_BITS: usize = 20;
+
+// Addresses and values have 64 bits, so masks can't be longer.
+pub fn check_mask(mask: &BitMask) -> Result<(), SimpleError> {
+    if mask.len() > 64 {
+        return Err(SimpleError::new(format!(
+            "mask has {} bits, at most 64 are supported",
+            mask.len()
+        )));
+    }
+    Ok(())
+}
+
+// The chip between the program and the memory: turns a write of `value` to
+// `address` under `mask` into the writes which actually happen.
+pub trait Decoder {
+    fn decode(
+        &self,
+        mask: &BitMask,
+        address: u64,
+        value: u64,
+    ) -> Result<Vec<(u64, u64)>, SimpleError>;
+}
+
+impl<F> Decoder for F
+where
+    F: Fn(&BitMask, u64, u64) -> Result<Vec<(u64, u64)>, SimpleError>,
+{
+    fn decode(
+        &self,
+        mask: &BitMask,
+        address: u64,
+        value: u64,
+    ) -> Result<Vec<(u64, u64)>, SimpleError> {
+        self(mask, address, value)
+    }
+}
+
+// Version 1: the mask changes the value, 1 and 0 overwrite the bit, X keeps
+// it.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ValueDecoder;
+
+impl Decoder for ValueDecoder {
+    fn decode(
+        &self,
+        mask: &BitMask,
+        address: u64,
+        value: u64,
+    ) -> Result<Vec<(u64, u64)>, SimpleError> {
+        check_mask(mask)?;
+        let mut value = value;
+        for (bit, onoff) in mask.iter().enumerate() {
+            match onoff {
+                Some(true) => value |= 1 << bit,
+                Some(false) => value &= !(1 << bit),
+                None => {}
+            }
+        }
+        Ok(vec![(address, value)])
+    }
+}
+
+// Version 2: the mask changes the address, 1 sets the bit, 0 keeps it and
+// X floats: the value is written to the addresses with both bit values.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AddressDecoder {
+    max_floating_bits: usize,
+}
+
+impl AddressDecoder {
+    // masks with more than `max_floating_bits` Xs are rejected, 64 allows
+    // every mask
+    pub fn new(max_floating_bits: usize) -> Self {
+        AddressDecoder { max_floating_bits }
+    }
+
+    pub fn max_floating_bits(&self) -> usize {
+        self.max_floating_bits
+    }
+}
+
+impl Default for AddressDecoder {
+    fn default() -> Self {
+        AddressDecoder::new(DEFAULT_MAX_FLOATING_BITS)
+    }
+}
+
+impl Decoder for AddressDecoder {
+    fn decode(
+        &self,
+        mask: &BitMask,
+        address: u64,
+        value: u64,
+    ) -> Result<Vec<(u64, u64)>, SimpleError> {
+        check_mask(mask)?;
+        let floating = mask.iter().filter(|onoff| onoff.is_none()).count();
+        if floating > self.max_floating_bits {
+            return Err(SimpleError::new(format!(
+                "too many floating bits: {}, at most {} are supported",
+                floating, self.max_floating_bits
+            )));
+        }
+
+        let mut addresses: Vec<u64> = vec![address];
+        for (bit, onoff) in mask.iter().enumerate() {
+            match onoff {
+                Some(true) => {
+                    for address in addresses.iter_mut() {
+                        *address |= 1 << bit;
+                    }
+                }
+                Some(false) => {}
+                None => {
+                    // every address once with the bit set and once without
+                    for address in addresses.iter_mut() {
+                        *address |= 1 << bit;
+                    }
+                    let cleared: Vec<u64> = addresses.iter().map(|a| a & !(1 << bit)).collect();
+                    addresses.extend(cleared);
+                }
+            }
+        }
+
+        Ok(addresses.into_iter().map(|a| (a, value)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(s: &str) -> BitMask {
+        s.chars()
+            .rev()
+            .map(|c| match c {
+                'X' => None,
+                '1' => Some(true),
+                _ => Some(false),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_value_decoder() {
+        let mask = mask("X1XXXX0X");
+
+        assert_eq!(ValueDecoder.decode(&mask, 8, 11), Ok(vec![(8, 73)]));
+        assert_eq!(ValueDecoder.decode(&mask, 7, 101), Ok(vec![(7, 101)]));
+        assert_eq!(ValueDecoder.decode(&mask, 8, 0), Ok(vec![(8, 64)]));
+    }
+
+    #[test]
+    fn test_address_decoder() {
+        let mut writes = AddressDecoder::default()
+            .decode(&mask("X1001X"), 42, 100)
+            .unwrap();
+        writes.sort_unstable();
+
+        assert_eq!(writes, vec![(26, 100), (27, 100), (58, 100), (59, 100)]);
+    }
+
+    #[test]
+    fn test_address_decoder_limit() {
+        let mask = mask(&"X".repeat(36));
+
+        assert_eq!(
+            AddressDecoder::default().decode(&mask, 0, 1),
+            Err(SimpleError::new(
+                "too many floating bits: 36, at most 20 are supported"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_address_decoder_raised_limit() {
+        let mask = mask(&"X".repeat(21));
+
+        assert_eq!(
+            AddressDecoder::default().decode(&mask, 0, 1),
+            Err(SimpleError::new(
+                "too many floating bits: 21, at most 20 are supported"
+            ))
+        );
+        assert_eq!(
+            AddressDecoder::new(21).decode(&mask, 0, 1).map(|w| w.len()),
+            Ok(1 << 21)
+        );
+        assert_eq!(
+            AddressDecoder::default()
+                .decode(&mask[1..].to_vec(), 0, 1)
+                .map(|w| w.len()),
+            Ok(1 << 20)
+        );
+    }
+
+    #[test]
+    fn test_mask_too_long() {
+        let mask = vec![Some(true); 65];
+        let expected = Err(SimpleError::new(
+            "mask has 65 bits, at most 64 are supported",
+        ));
+
+        assert_eq!(ValueDecoder.decode(&mask, 0, 0), expected);
+        assert_eq!(AddressDecoder::default().decode(&mask, 0, 0), expected);
+        assert_eq!(
+            ValueDecoder.decode(&vec![Some(true); 64], 0, 0),
+            Ok(vec![(0, u64::MAX)])
+        );
+    }
+
+    #[test]
+    fn test_custom_decoder() {
+        let ignore_mask = |_: &BitMask, address: u64, value: u64| Ok(vec![(address, value)]);
+
+        assert_eq!(ignore_mask.decode(&mask("1111"), 3, 5), Ok(vec![(3, 5)]));
+    }
+}
@@ -0,0 +1,7 @@
+mod commands;
+mod computer;
+mod decoder;
+
+pub use commands::{BitMask, Command};
+pub use computer::DockingComputer;
+pub use decoder::{check_mask, AddressDecoder, Decoder, ValueDecoder, DEFAULT_MAX_FLOATING_BITS};
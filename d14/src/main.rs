@@ -1,8 +1,5 @@
-mod commands;
-use commands::{BitMask, Command};
-
+use d14::{AddressDecoder, Command, Decoder, DockingComputer, ValueDecoder};
 use simple_error::SimpleError;
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
@@ -18,86 +15,22 @@ fn main() {
     println!("part 2: {:?}", part_2(input.iter()));
 }
 
-fn part_1<'a>(commands: impl Iterator<Item = &'a Command>) -> Result<u64, SimpleError> {
-    let mut memory: HashMap<u64, u64> = HashMap::new();
-
-    let mut current_mask = BitMask::new();
-
-    for command in commands {
-        match command {
-            Command::SetMask(mask) => current_mask = mask.iter().cloned().collect(),
-            Command::SetValue(adr, v) => {
-                let mut value = *v;
-
-                for (bit, onoff) in current_mask.iter().enumerate() {
-                    match onoff {
-                        Some(true) => {
-                            // set a bit
-                            let mask = 1 << bit;
-                            value |= mask;
-                        }
-                        Some(false) => {
-                            // remove a bit
-                            let mask = !(1 << bit);
-                            value &= mask;
-                        }
-                        None => {}
-                    }
-                }
-                memory.insert(*adr, value);
-            }
-        }
-    }
-
-    Ok(memory.values().filter(|&v| *v > 0).sum())
+fn run<'a>(
+    decoder: impl Decoder,
+    commands: impl Iterator<Item = &'a Command>,
+) -> Result<u128, SimpleError> {
+    let mut computer = DockingComputer::new(decoder);
+    computer.run(commands)?;
+    Ok(computer.sum())
 }
 
-fn part_2<'a>(commands: impl Iterator<Item = &'a Command>) -> Result<u64, SimpleError> {
-    let mut memory: HashMap<u64, u64> = HashMap::new();
-    let mut current_mask = BitMask::new();
-
-    for command in commands {
-        match command {
-            Command::SetMask(mask) => current_mask = mask.iter().cloned().collect(),
-            Command::SetValue(a, value) => {
-                let mut adresses: Vec<u64> = vec![*a];
-
-                for (bit, onoff) in current_mask.iter().enumerate() {
-                    match onoff {
-                        Some(true) => {
-                            // 1 means set a bit in all addresses
-                            let mask = 1 << bit;
-                            for i in 0..adresses.len() {
-                                adresses[i] |= mask;
-                            }
-                        }
-                        Some(false) => {} // do nothing for 0 bit in mask
-                        None => {
-                            // X means we need both possible values for
-                            // the addresses. So:
-
-                            // first set bit in all addresses,
-                            let mask_set = 1 << bit;
-                            for i in 0..adresses.len() {
-                                adresses[i] |= mask_set;
-                            }
-
-                            // then duplicate with removed bit
-                            let mask_del = !mask_set;
-                            let new_adresses: Vec<u64> =
-                                adresses.iter().map(|v| v & mask_del).collect();
-                            adresses.extend(new_adresses);
-                        }
-                    }
-                }
-
-                // set the same value for all addresses in the list
-                memory.extend(adresses.iter().map(|a| (*a, *value)));
-            }
-        }
-    }
+fn part_1<'a>(commands: impl Iterator<Item = &'a Command>) -> Result<u128, SimpleError> {
+    run(ValueDecoder, commands)
+}
 
-    Ok(memory.values().filter(|&v| *v > 0).sum())
+fn part_2<'a>(commands: impl Iterator<Item = &'a Command>) -> Result<u128, SimpleError> {
+    // like the puzzle, any number of floating bits
+    run(AddressDecoder::new(64), commands)
 }
 
 #[cfg(test)]